@@ -0,0 +1,318 @@
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+use crate::{CLIConfig, OutputFormat};
+
+/// Builds a [`CLIConfig`] for linking from code instead of from the command line
+///
+/// Everything starts out the same as if kld were run with only an input and an output path, so
+/// only the settings that differ need to be given:
+///
+/// ```
+/// use klinker::CLIConfig;
+///
+/// let config = CLIConfig::builder("program.ksm")
+///     .with_input_path("program.ko")
+///     .with_fold_identical(true)
+///     .build();
+///
+/// assert_eq!(config.entry_point, "_start");
+/// ```
+#[derive(Debug, Clone)]
+pub struct CLIConfigBuilder {
+    config: CLIConfig,
+}
+
+impl CLIConfigBuilder {
+    /// Creates a builder for a config that writes to the given output path
+    pub fn new(output_path: impl Into<PathBuf>) -> Self {
+        Self {
+            config: CLIConfig {
+                input_paths: Vec::new(),
+                output_path: Some(output_path.into()),
+                output_format: OutputFormat::default(),
+                config_file: None,
+                entry_point: String::from("_start"),
+                entry_offset: 0,
+                shared: false,
+                hybrid: false,
+                whole_archive: Vec::new(),
+                keep_symbols: Vec::new(),
+                search_paths: Vec::new(),
+                allow_duplicate_inputs: false,
+                fold_identical: false,
+                inline_threshold: None,
+                allow_multiple_definition: false,
+                split_sections: false,
+                sort_functions: false,
+                layout: None,
+                align_functions: None,
+                resolve_direct_symrefs: false,
+                require_reld: false,
+                comment: None,
+                comment_from: None,
+                emit_json: None,
+                emit_exports: None,
+                emit_debug: None,
+                hide_symbols: Vec::new(),
+                wrap_symbols: Vec::new(),
+                no_undefined: false,
+                warn_builtins: false,
+                print_symbols: false,
+                print_gc_graph: false,
+                print_exports: false,
+                print_stats: false,
+                trace_symbols: Vec::new(),
+                list_sources: false,
+                validate: false,
+                max_size: None,
+                warn_addr_width: false,
+                addr_width: None,
+                ksm_magic: None,
+                werror: false,
+                verbosity: 0,
+                quiet: false,
+            },
+        }
+    }
+
+    /// Adds a file to be linked. Response files are read the same as on the command line.
+    pub fn with_input_path(mut self, input_path: impl Into<PathBuf>) -> Self {
+        self.config.input_paths.push(input_path.into());
+        self
+    }
+
+    /// Adds several files to be linked
+    pub fn with_input_paths<P: Into<PathBuf>>(
+        mut self,
+        input_paths: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.config
+            .input_paths
+            .extend(input_paths.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_output_format(mut self, output_format: OutputFormat) -> Self {
+        self.config.output_format = output_format;
+        self
+    }
+
+    pub fn with_entry_point(mut self, entry_point: impl Into<String>) -> Self {
+        self.config.entry_point = entry_point.into();
+        self
+    }
+
+    pub fn with_entry_offset(mut self, entry_offset: usize) -> Self {
+        self.config.entry_offset = entry_offset;
+        self
+    }
+
+    pub fn with_shared(mut self, shared: bool) -> Self {
+        self.config.shared = shared;
+        self
+    }
+
+    pub fn with_hybrid(mut self, hybrid: bool) -> Self {
+        self.config.hybrid = hybrid;
+        self
+    }
+
+    /// Adds an input whose global functions are all kept, whether they are referenced or not
+    pub fn with_whole_archive(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.whole_archive.push(path.into());
+        self
+    }
+
+    /// Adds a global function that is kept whether it is referenced or not
+    pub fn with_keep_symbol(mut self, name: impl Into<String>) -> Self {
+        self.config.keep_symbols.push(name.into());
+        self
+    }
+
+    /// Adds a directory to look for inputs in when they aren't found at their given path
+    pub fn with_search_path(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.search_paths.push(dir.into());
+        self
+    }
+
+    pub fn with_allow_duplicate_inputs(mut self, allow_duplicate_inputs: bool) -> Self {
+        self.config.allow_duplicate_inputs = allow_duplicate_inputs;
+        self
+    }
+
+    pub fn with_fold_identical(mut self, fold_identical: bool) -> Self {
+        self.config.fold_identical = fold_identical;
+        self
+    }
+
+    /// Inlines calls to functions with at most the given number of instructions. This is
+    /// experimental.
+    pub fn with_inline_threshold(mut self, inline_threshold: usize) -> Self {
+        self.config.inline_threshold = Some(inline_threshold);
+        self
+    }
+
+    pub fn with_allow_multiple_definition(mut self, allow_multiple_definition: bool) -> Self {
+        self.config.allow_multiple_definition = allow_multiple_definition;
+        self
+    }
+
+    pub fn with_split_sections(mut self, split_sections: bool) -> Self {
+        self.config.split_sections = split_sections;
+        self
+    }
+
+    pub fn with_sort_functions(mut self, sort_functions: bool) -> Self {
+        self.config.sort_functions = sort_functions;
+        self
+    }
+
+    /// Lays out the functions listed in the given file first, in the order they are listed
+    pub fn with_layout(mut self, layout: impl Into<PathBuf>) -> Self {
+        self.config.layout = Some(layout.into());
+        self
+    }
+
+    pub fn with_align_functions(mut self, align_functions: NonZeroUsize) -> Self {
+        self.config.align_functions = Some(align_functions);
+        self
+    }
+
+    pub fn with_resolve_direct_symrefs(mut self, resolve_direct_symrefs: bool) -> Self {
+        self.config.resolve_direct_symrefs = resolve_direct_symrefs;
+        self
+    }
+
+    pub fn with_require_reld(mut self, require_reld: bool) -> Self {
+        self.config.require_reld = require_reld;
+        self
+    }
+
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.config.comment = Some(comment.into());
+        self
+    }
+
+    pub fn with_comment_from(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.comment_from = Some(path.into());
+        self
+    }
+
+    pub fn with_emit_json(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.emit_json = Some(path.into());
+        self
+    }
+
+    pub fn with_emit_exports(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.emit_exports = Some(path.into());
+        self
+    }
+
+    pub fn with_emit_debug(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.emit_debug = Some(path.into());
+        self
+    }
+
+    /// Keeps a global symbol out of a shared or hybrid object's exports
+    pub fn with_hidden_symbol(mut self, name: impl Into<String>) -> Self {
+        self.config.hide_symbols.push(name.into());
+        self
+    }
+
+    /// Redirects references to a symbol to __wrap_ followed by its name
+    pub fn with_wrap_symbol(mut self, name: impl Into<String>) -> Self {
+        self.config.wrap_symbols.push(name.into());
+        self
+    }
+
+    pub fn with_no_undefined(mut self, no_undefined: bool) -> Self {
+        self.config.no_undefined = no_undefined;
+        self
+    }
+
+    pub fn with_warn_builtins(mut self, warn_builtins: bool) -> Self {
+        self.config.warn_builtins = warn_builtins;
+        self
+    }
+
+    pub fn with_print_symbols(mut self, print_symbols: bool) -> Self {
+        self.config.print_symbols = print_symbols;
+        self
+    }
+
+    pub fn with_print_gc_graph(mut self, print_gc_graph: bool) -> Self {
+        self.config.print_gc_graph = print_gc_graph;
+        self
+    }
+
+    pub fn with_print_exports(mut self, print_exports: bool) -> Self {
+        self.config.print_exports = print_exports;
+        self
+    }
+
+    pub fn with_print_stats(mut self, print_stats: bool) -> Self {
+        self.config.print_stats = print_stats;
+        self
+    }
+
+    /// Reports every input that references or defines a symbol with this name
+    pub fn with_trace_symbol(mut self, name: impl Into<String>) -> Self {
+        self.config.trace_symbols.push(name.into());
+        self
+    }
+
+    pub fn with_list_sources(mut self, list_sources: bool) -> Self {
+        self.config.list_sources = list_sources;
+        self
+    }
+
+    pub fn with_validate(mut self, validate: bool) -> Self {
+        self.config.validate = validate;
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.config.max_size = Some(max_size);
+        self
+    }
+
+    pub fn with_warn_addr_width(mut self, warn_addr_width: bool) -> Self {
+        self.config.warn_addr_width = warn_addr_width;
+        self
+    }
+
+    /// How many bytes every operand takes, from 1 to 4
+    pub fn with_addr_width(mut self, addr_width: u8) -> Self {
+        self.config.addr_width = Some(addr_width);
+        self
+    }
+
+    /// Writes a non-standard magic number in the KSM header. This is experimental, kOS won't
+    /// load the output.
+    pub fn with_ksm_magic(mut self, magic: u32) -> Self {
+        self.config.ksm_magic = Some(magic);
+        self
+    }
+
+    pub fn with_werror(mut self, werror: bool) -> Self {
+        self.config.werror = werror;
+        self
+    }
+
+    /// How much to log, see [`LogLevel`](crate::driver::log::LogLevel)
+    pub fn with_verbosity(mut self, verbosity: u8) -> Self {
+        self.config.verbosity = verbosity;
+        self
+    }
+
+    /// Prints nothing but errors, see [`Reporter`](crate::driver::log::Reporter)
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.config.quiet = quiet;
+        self
+    }
+
+    pub fn build(self) -> CLIConfig {
+        self.config
+    }
+}
@@ -0,0 +1,90 @@
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::driver::errors::{LinkError, LinkResult};
+use crate::CLIConfig;
+
+/// The config file that is read when --config isn't given, if there is one in the current directory
+pub static DEFAULT_CONFIG_FILE: &str = "kld.toml";
+
+/// Default options read from a config file, such as kld.toml:
+///
+/// ```toml
+/// # Options for every link in this project
+/// entry_point = "main"
+/// shared = false
+/// search_paths = ["lib", "vendor/lib"]
+/// ```
+///
+/// Relative search paths are relative to the directory the file is in. Any key that isn't one of
+/// these options is an error, so that a misspelled option isn't silently ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub entry_point: Option<String>,
+    pub shared: Option<bool>,
+    #[serde(default)]
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl ConfigFile {
+    /// Reads a config file from the given path
+    pub fn read(path: impl AsRef<Path>) -> LinkResult<Self> {
+        let path = path.as_ref();
+
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| LinkError::IOError(OsString::from(path.as_os_str()), e.kind()))?;
+
+        let mut config_file = ConfigFile::parse(&contents).map_err(|(line, message)| {
+            LinkError::ConfigFileError(OsString::from(path.as_os_str()), line, message)
+        })?;
+
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+
+        for search_path in config_file.search_paths.iter_mut() {
+            *search_path = base_dir.join(&search_path);
+        }
+
+        Ok(config_file)
+    }
+
+    /// Parses the contents of a config file. Returns the line number and what is wrong with it if
+    /// it can't be parsed.
+    pub fn parse(contents: &str) -> Result<Self, (usize, String)> {
+        toml::from_str(contents).map_err(|e: toml::de::Error| {
+            // Errors without a span are about the file as a whole, so they are put on the first line
+            let line_number = e
+                .span()
+                .map(|span| contents[..span.start].matches('\n').count() + 1)
+                .unwrap_or(1);
+
+            (line_number, e.message().to_owned())
+        })
+    }
+
+    /// Fills in every option that wasn't given on the command line with the value from this file.
+    /// Search paths from the command line are searched first, and then the ones from this file.
+    pub fn apply(&self, config: &mut CLIConfig, matches: &ArgMatches) {
+        let from_command_line =
+            |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if let Some(entry_point) = &self.entry_point {
+            if !from_command_line("entry_point") {
+                config.entry_point = entry_point.to_owned();
+            }
+        }
+
+        if let Some(shared) = self.shared {
+            if !from_command_line("shared") {
+                config.shared = shared;
+            }
+        }
+
+        config
+            .search_paths
+            .extend(self.search_paths.iter().cloned());
+    }
+}
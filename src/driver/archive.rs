@@ -0,0 +1,236 @@
+use std::fmt::{Display, Formatter};
+use std::slice::Iter;
+
+// 'K' 'A' 'R' 1 but in little-endian form
+const KAR_MAGIC_NUMBER: u32 = 0x0152414b;
+
+/// An in-memory representation of a KerbalObject archive (.kar) file
+///
+/// An archive is a header containing the magic number and the number of members, followed by an
+/// index of every member's name, offset, and size, followed by the raw bytes of each member KO file.
+/// All integers are little-endian.
+///
+/// ```text
+/// magic:        u32
+/// member_count: u32
+/// index:        [name_len: u16, name: [u8; name_len], offset: u32, size: u32; member_count]
+/// data:         [u8]
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Archive {
+    members: Vec<ArchiveMember>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    name: String,
+    data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum ArchiveParseError {
+    InvalidMagicError(u32),
+    UnexpectedEndError(usize),
+    InvalidMemberNameError(usize),
+    MemberOutOfBoundsError(String),
+}
+
+#[derive(Debug)]
+pub enum ArchiveWriteError {
+    MemberNameTooLongError(String),
+    ArchiveTooLargeError(String),
+}
+
+impl Archive {
+    pub fn new() -> Self {
+        Archive {
+            members: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, data: Vec<u8>) {
+        self.members.push(ArchiveMember::new(name, data));
+    }
+
+    pub fn members(&self) -> Iter<'_, ArchiveMember> {
+        self.members.iter()
+    }
+
+    pub fn parse(source: &[u8]) -> Result<Self, ArchiveParseError> {
+        let mut cursor = 0;
+
+        let magic = Archive::read_u32(source, &mut cursor)?;
+
+        if magic != KAR_MAGIC_NUMBER {
+            return Err(ArchiveParseError::InvalidMagicError(magic));
+        }
+
+        let member_count = Archive::read_u32(source, &mut cursor)? as usize;
+
+        // The count comes from the file, so it can't be trusted to size the allocation. Each index
+        // entry takes at least 10 bytes, which is as many members as there could possibly be.
+        let mut members = Vec::with_capacity(member_count.min((source.len() - cursor) / 10));
+
+        for member_index in 0..member_count {
+            let name_len = Archive::read_u16(source, &mut cursor)? as usize;
+            let name_bytes = Archive::read_slice(source, &mut cursor, name_len)?;
+            let name = String::from_utf8(name_bytes.to_vec())
+                .map_err(|_| ArchiveParseError::InvalidMemberNameError(member_index))?;
+
+            let offset = Archive::read_u32(source, &mut cursor)? as usize;
+            let size = Archive::read_u32(source, &mut cursor)? as usize;
+
+            let data = offset
+                .checked_add(size)
+                .and_then(|end| source.get(offset..end))
+                .ok_or_else(|| ArchiveParseError::MemberOutOfBoundsError(name.to_owned()))?
+                .to_vec();
+
+            members.push(ArchiveMember { name, data });
+        }
+
+        Ok(Archive { members })
+    }
+
+    /// Writes the archive to the end of the buffer. Member names have to be at most u16::MAX bytes
+    /// long, and every member has to start and end within the first u32::MAX bytes of the archive,
+    /// because that is as large as the index can describe. Nothing is written if either isn't true.
+    pub fn write(&self, buf: &mut Vec<u8>) -> Result<(), ArchiveWriteError> {
+        // 4 for the magic number, 4 for the member count
+        let index_size: usize = 8 + self
+            .members
+            .iter()
+            .map(|member| 2 + member.name.len() + 4 + 4)
+            .sum::<usize>();
+
+        let mut index = Vec::with_capacity(self.members.len());
+        let mut offset = index_size;
+
+        for member in self.members.iter() {
+            let name_len = u16::try_from(member.name.len())
+                .map_err(|_| ArchiveWriteError::MemberNameTooLongError(member.name.to_owned()))?;
+
+            let end = offset + member.data.len();
+
+            if u32::try_from(end).is_err() {
+                return Err(ArchiveWriteError::ArchiveTooLargeError(
+                    member.name.to_owned(),
+                ));
+            }
+
+            index.push((name_len, offset as u32, member.data.len() as u32));
+
+            offset = end;
+        }
+
+        // Every index entry is at least 10 bytes, so if every member fits, so does the count
+        buf.extend_from_slice(&KAR_MAGIC_NUMBER.to_le_bytes());
+        buf.extend_from_slice(&(self.members.len() as u32).to_le_bytes());
+
+        for (member, (name_len, offset, size)) in self.members.iter().zip(index) {
+            buf.extend_from_slice(&name_len.to_le_bytes());
+            buf.extend_from_slice(member.name.as_bytes());
+            buf.extend_from_slice(&offset.to_le_bytes());
+            buf.extend_from_slice(&size.to_le_bytes());
+        }
+
+        for member in self.members.iter() {
+            buf.extend_from_slice(&member.data);
+        }
+
+        Ok(())
+    }
+
+    fn read_slice<'a>(
+        source: &'a [u8],
+        cursor: &mut usize,
+        len: usize,
+    ) -> Result<&'a [u8], ArchiveParseError> {
+        let slice = source
+            .get(*cursor..*cursor + len)
+            .ok_or(ArchiveParseError::UnexpectedEndError(*cursor))?;
+
+        *cursor += len;
+
+        Ok(slice)
+    }
+
+    fn read_u16(source: &[u8], cursor: &mut usize) -> Result<u16, ArchiveParseError> {
+        let bytes = Archive::read_slice(source, cursor, 2)?;
+
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(source: &[u8], cursor: &mut usize) -> Result<u32, ArchiveParseError> {
+        let bytes = Archive::read_slice(source, cursor, 4)?;
+
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+impl ArchiveMember {
+    pub fn new(name: impl Into<String>, data: Vec<u8>) -> Self {
+        ArchiveMember {
+            name: name.into(),
+            data,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl std::error::Error for ArchiveParseError {}
+
+impl Display for ArchiveParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveParseError::InvalidMagicError(magic) => {
+                write!(f, "Not a KerbalObject archive, found magic {:#010x}", magic)
+            }
+            ArchiveParseError::UnexpectedEndError(position) => {
+                write!(f, "Archive ended unexpectedly at byte {}", position)
+            }
+            ArchiveParseError::InvalidMemberNameError(member_index) => {
+                write!(f, "Archive member {} name is invalid UTF-8", member_index)
+            }
+            ArchiveParseError::MemberOutOfBoundsError(name) => {
+                write!(
+                    f,
+                    "Archive member {} extends past the end of the file",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArchiveWriteError {}
+
+impl Display for ArchiveWriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveWriteError::MemberNameTooLongError(name) => {
+                write!(
+                    f,
+                    "Archive member name is longer than {} bytes: {}",
+                    u16::MAX,
+                    name
+                )
+            }
+            ArchiveWriteError::ArchiveTooLargeError(name) => {
+                write!(
+                    f,
+                    "Archive member {} would end past byte {}, which is as large as an archive can be",
+                    name,
+                    u32::MAX
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+/// The names of functions that kOS provides to every script. A global function with one of these
+/// names shadows the built-in for anything that calls it by name.
+pub const BUILTIN_NAMES: &[&str] = &[
+    // Math
+    "abs",
+    "ceiling",
+    "floor",
+    "ln",
+    "log10",
+    "max",
+    "min",
+    "mod",
+    "random",
+    "randomseed",
+    "round",
+    "sqrt",
+    "char",
+    "unchar",
+    // Trigonometry
+    "sin",
+    "cos",
+    "tan",
+    "arcsin",
+    "arccos",
+    "arctan",
+    "arctan2",
+    // Vectors and directions
+    "v",
+    "r",
+    "q",
+    "heading",
+    "lookdirup",
+    "angleaxis",
+    "rotatefromto",
+    "vcrs",
+    "vectorcrossproduct",
+    "vdot",
+    "vectordotproduct",
+    "vxcl",
+    "vectorexclude",
+    "vang",
+    "vectorangle",
+    // Collections
+    "list",
+    "lex",
+    "lexicon",
+    "queue",
+    "stack",
+    "uniqueset",
+    "range",
+    // Terminal and display
+    "print",
+    "printat",
+    "clearscreen",
+    "hudtext",
+    "vecdraw",
+    "vecdrawargs",
+    "clearvecdraws",
+    "gui",
+    "clearguis",
+    "rgb",
+    "rgba",
+    "hsv",
+    "hsva",
+    // Vessels and orbits
+    "stage",
+    "body",
+    "vessel",
+    "node",
+    "latlng",
+    "positionat",
+    "velocityat",
+    "orbitat",
+    "createorbit",
+    "processor",
+    "transfer",
+    "transferall",
+    "warpto",
+    // Files and volumes
+    "volume",
+    "path",
+    "scriptpath",
+    "exists",
+    "open",
+    "create",
+    "createdir",
+    "copypath",
+    "movepath",
+    "deletepath",
+    "runpath",
+    "runoncepath",
+    // Time
+    "time",
+    "timestamp",
+    "timespan",
+    // Sound
+    "note",
+    "slidenote",
+    "getvoice",
+    "stopallvoices",
+    // Everything else
+    "constant",
+    "reboot",
+    "shutdown",
+    "debugdump",
+    "profileresult",
+    "droppriority",
+];
+
+/// If a function name is the name of a kOS built-in. kOS ignores case, so this does too.
+pub fn is_builtin(name: &str) -> bool {
+    BUILTIN_NAMES
+        .iter()
+        .any(|builtin| builtin.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::tables::ObjectData;
+
+/// A cache of processed object files that can be shared between links, so that inputs that
+/// haven't changed since the last link don't have to be read and processed again.
+///
+/// Entries are keyed by path, and are only used if the file's modification time and length are
+/// the same as when it was cached. Cloning an ObjectCache gives another handle to the same cache.
+///
+/// When relinking after a change, the files that are known to have changed can be invalidated
+/// so they are always processed again, even if their modification time didn't change. Only
+/// processing is reused, every link still lays out and emits the whole program.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectCache {
+    entries: Arc<Mutex<HashMap<PathBuf, CacheEntry>>>,
+    hits: Arc<AtomicUsize>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    stamp: FileStamp,
+    resolve_direct_symrefs: bool,
+    require_reld: bool,
+    object_data: ObjectData,
+}
+
+/// What is known about a file on disk that changes whenever the file does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl ObjectCache {
+    pub fn new() -> Self {
+        ObjectCache {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The number of object files currently cached
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Removes the entry for a file that has changed, so the next link processes it again.
+    /// The path has to be the same one the file was added by. Returns true if it was cached.
+    pub fn invalidate(&self, path: impl AsRef<Path>) -> bool {
+        self.entries
+            .lock()
+            .map(|mut entries| entries.remove(path.as_ref()).is_some())
+            .unwrap_or(false)
+    }
+
+    /// How many times an input has been taken from the cache instead of being processed
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn get(
+        &self,
+        path: &Path,
+        stamp: FileStamp,
+        resolve_direct_symrefs: bool,
+        require_reld: bool,
+    ) -> Option<ObjectData> {
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.get(path)?;
+
+        // The same file processed differently is not the same result
+        if entry.stamp == stamp
+            && entry.resolve_direct_symrefs == resolve_direct_symrefs
+            && entry.require_reld == require_reld
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+
+            Some(entry.object_data.clone())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn insert(
+        &self,
+        path: PathBuf,
+        stamp: FileStamp,
+        resolve_direct_symrefs: bool,
+        require_reld: bool,
+        object_data: ObjectData,
+    ) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                path,
+                CacheEntry {
+                    stamp,
+                    resolve_direct_symrefs,
+                    require_reld,
+                    object_data,
+                },
+            );
+        }
+    }
+}
+
+impl FileStamp {
+    /// Returns None if the file's metadata can't be read, in which case it can't be cached
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+
+        Some(FileStamp {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use kerbalobjects::ksm::sections::{DebugEntry, DebugRange};
+use std::fmt::{Display, Formatter, Write};
+
+use super::validate::label;
+
+/// Creates a debug entry for a source line from the ranges of bytes that were generated for it.
+///
+/// kOS expects every range to be non-empty and the ranges of an entry to be in order, so ranges
+/// whose end doesn't come after their start are dropped, and ranges that overlap or touch are
+/// merged together. Returns None if there are no ranges left.
+pub fn debug_entry(
+    line_number: isize,
+    ranges: impl IntoIterator<Item = DebugRange>,
+) -> Option<DebugEntry> {
+    let mut ranges: Vec<DebugRange> = ranges
+        .into_iter()
+        .filter(|range| range.start < range.end)
+        .collect();
+
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<DebugRange> = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    if merged.is_empty() {
+        None
+    } else {
+        Some(DebugEntry::new(line_number).with_ranges(merged))
+    }
+}
+
+/// The instructions of one function in a linked file, and where they came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMapEntry {
+    /// The absolute index of the function's first instruction
+    pub start: usize,
+    /// The absolute index just past the function's last instruction
+    pub end: usize,
+    pub func_name: String,
+    pub source_file_name: String,
+}
+
+impl SourceMapEntry {
+    /// If the instruction at the given absolute index is part of this function
+    pub fn contains(&self, offset: usize) -> bool {
+        (self.start..self.end).contains(&offset)
+    }
+}
+
+impl Display for SourceMapEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in {}", self.func_name, self.source_file_name)
+    }
+}
+
+/// Creates the contents of the separate debug file written by --emit-debug.
+///
+/// Each retained function is on its own line in the order it appears in the output, as the labels
+/// of its first instruction and of the instruction after its last, its name, and the source file
+/// it came from, separated by tabs. KO files don't record source lines, so there are none to map
+/// to yet.
+pub fn debug_map(source_map: &[SourceMapEntry]) -> String {
+    let mut map = String::new();
+
+    for entry in source_map {
+        // Writing to a String can't fail
+        let _ = writeln!(
+            map,
+            "{}\t{}\t{}\t{}",
+            label(entry.start),
+            label(entry.end),
+            entry.func_name,
+            entry.source_file_name
+        );
+    }
+
+    map
+}
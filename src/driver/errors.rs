@@ -1,4 +1,7 @@
+use super::archive::ArchiveParseError;
 use kerbalobjects::ko::errors::KOParseError;
+use kerbalobjects::ko::symbols::SymType;
+use kerbalobjects::Opcode;
 use std::{
     error::Error,
     ffi::OsString,
@@ -10,34 +13,78 @@ pub type LinkResult<T> = Result<T, LinkError>;
 #[derive(Debug)]
 pub enum LinkError {
     IOError(OsString, std::io::ErrorKind),
-    FileReadError(OsString, KOParseError),
+    ConfigFileError(OsString, usize, String),
+    FileWriteError(OsString, std::io::ErrorKind),
+    SummaryWriteError(OsString, serde_json::Error),
+    EmptyInputFileError(String),
+    FileReadError(OsString, KOParseError, usize, Option<String>),
+    ArchiveReadError(OsString, ArchiveParseError),
     InvalidPathError(String),
+    NoInputFiles,
+    MissingOutputPathError,
+    InputNotFoundError(String, Vec<String>),
+    OutputTooLargeError(usize, usize, usize),
+    AddrWidthTooSmallError(u8, usize, usize),
     MissingSectionError(String, String),
-    MissingFileSymbolNameError(String),
+    MissingFileSymbolNameError(String, usize, usize),
     FileContextError(FileErrorContext, ProcessingError),
     FuncContextError(FuncErrorContext, ProcessingError),
     MissingFileSymbolError(String),
+    DuplicateFileSymbolError(String, String, String),
     MissingFunctionNameError(String, String, u16),
     StringConversionError,
     InternalError(String),
     DataIndexOverflowError,
-    MissingEntryPointError(String),
+    MissingEntryPointError(String, Option<String>),
+    LocalEntryPointError(String, String),
     MissingInitFunctionError,
+    HiddenInitFunctionError,
     EntryInSharedError,
+    InitEntryPointError,
+    InvalidEntryOffsetError(usize, usize),
+    CommentSourceNotFoundError(String),
+    InitCycleError(Vec<String>),
     UnresolvedExternalSymbolError(String),
-    InvalidSymbolRefError(String, usize, u64),
+    LocalDefinitionError(String, String),
+    MissingWrapSymbolError(String),
+    MissingKeepSymbolError(String),
+    InvalidSymbolRefError(FuncErrorContext, usize, String),
+    WarningsAsErrors(Vec<String>),
+    ValidationError(String),
+    MergeError(String),
+    MergeEntryPointError,
+    WorkerPanic(String, String),
 }
 
 #[derive(Debug)]
 pub enum ProcessingError {
     MissingNameError(String),
-    InvalidDataIndexError(usize, usize),
-    InvalidSymbolIndexError(usize, usize),
+    InvalidDataIndexError(usize, usize, usize),
+    InvalidSymbolIndexError(usize, usize, usize),
     MissingSymbolNameError(usize, usize),
     InvalidSymbolDataIndexError(String, usize),
-    DuplicateSymbolError(String, String),
-    FuncMissingSymbolError,
+    DuplicateSymbolError(
+        String,
+        SymType,
+        SymType,
+        Box<DefinitionContext>,
+        Box<DefinitionContext>,
+    ),
+    FuncMissingSymbolError(String, u16),
     FuncSymbolInvalidTypeError,
+    InvalidRelocationOperandError(usize, usize),
+    InvalidRelocationInstrError(usize, usize),
+    OpcodeArityMismatchError(usize, Opcode, usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum LinkWarning {
+    EmptyEntryPointWarning(String),
+    ArgIndexWidthWarning(usize, usize),
+    DuplicateInputWarning(String),
+    MultipleDefinitionWarning(String, Box<DefinitionContext>, Box<DefinitionContext>),
+    BuiltinShadowWarning(String, String),
+    LayoutFunctionWarning(String),
 }
 
 impl Error for LinkError {}
@@ -54,12 +101,89 @@ impl Display for LinkError {
                     std::io::Error::from(*error_kind)
                 )
             }
-            LinkError::FileReadError(file_name, e) => {
-                write!(f, "Link error: Error reading {:?}, {}", file_name, e)
+            LinkError::ConfigFileError(file_name, line_number, message) => {
+                write!(
+                    f,
+                    "Error reading config file {:?}, line {}: {}",
+                    file_name, line_number, message
+                )
+            }
+            LinkError::FileWriteError(file_name, error_kind) => {
+                write!(
+                    f,
+                    "Link error: I/O error writing {:?}, {}",
+                    file_name,
+                    std::io::Error::from(*error_kind)
+                )
+            }
+            LinkError::SummaryWriteError(file_name, e) => {
+                write!(
+                    f,
+                    "Link error: Error writing JSON summary to {:?}, {}",
+                    file_name, e
+                )
+            }
+            LinkError::EmptyInputFileError(file_name) => {
+                write!(
+                    f,
+                    "Link error: Input file {} is empty, so it is not a KO file",
+                    file_name
+                )
+            }
+            LinkError::FileReadError(file_name, e, offset, section) => {
+                write!(f, "Link error: Error reading {:?}", file_name)?;
+
+                match section {
+                    Some(section) => write!(f, " in {} at byte {}", section, offset)?,
+                    None => write!(f, " at byte {}", offset)?,
+                }
+
+                write!(f, ", {}", e)
+            }
+            LinkError::ArchiveReadError(file_name, e) => {
+                write!(
+                    f,
+                    "Link error: Error reading archive {:?}, {}",
+                    file_name, e
+                )
             }
             LinkError::InvalidPathError(path) => {
                 write!(f, "Link error: I/O error, path {} invalid", path)
             }
+            LinkError::MissingOutputPathError => {
+                write!(
+                    f,
+                    "Link error: No output path was given, use -o to give one"
+                )
+            }
+            LinkError::InputNotFoundError(input_name, searched) => {
+                write!(
+                    f,
+                    "Link error: Input {} was not found, looked for it at: {}",
+                    input_name,
+                    searched.join(", ")
+                )
+            }
+            LinkError::NoInputFiles => {
+                write!(
+                    f,
+                    "Link error: No input files to link, at least one object file must be provided"
+                )
+            }
+            LinkError::AddrWidthTooSmallError(addr_width, index_bytes, arg_section_size) => {
+                write!(
+                    f,
+                    "Link error: operands can't be {} byte(s) wide, the argument section is {} bytes so they need at least {}",
+                    addr_width, arg_section_size, index_bytes
+                )
+            }
+            LinkError::OutputTooLargeError(size, max_size, uncompressed_size) => {
+                write!(
+                    f,
+                    "Link error: Output is {} bytes ({} uncompressed), which is larger than the maximum of {} bytes",
+                    size, uncompressed_size, max_size
+                )
+            }
             LinkError::StringConversionError => {
                 write!(f, "Link error: File name is invalid UTF-8")
             }
@@ -68,13 +192,35 @@ impl Display for LinkError {
                     f,
                     "Error linking {}.\nMissing required section {}",
                     file_name, section_name
-                )
+                )?;
+
+                match section_name.as_str() {
+                    ".reld" => write!(
+                        f,
+                        "\nNote: --require-reld was given, and this file's functions could reference its symbols only through relocations"
+                    ),
+                    _ => write!(
+                        f,
+                        "\nThis file may not be a fully assembled KO file, was it produced by kasm?"
+                    ),
+                }
             }
             LinkError::MissingFileSymbolError(file_name) => {
                 write!(f, "Error linking {}.\nMissing FILE symbol", file_name)
             }
-            LinkError::MissingFileSymbolNameError(file_name) => {
-                write!(f, "Error linking {}.\nMissing FILE symbol name", file_name)
+            LinkError::DuplicateFileSymbolError(file_name, first, second) => {
+                write!(
+                    f,
+                    "Error linking {}.\nMore than one FILE symbol, \"{}\" and \"{}\". The file may have been concatenated with another",
+                    file_name, first, second
+                )
+            }
+            LinkError::MissingFileSymbolNameError(file_name, symbol_index, name_index) => {
+                write!(
+                    f,
+                    "Error linking {}.\nMissing FILE symbol name, symbol at index {} references invalid name index {}",
+                    file_name, symbol_index, name_index
+                )
             }
             LinkError::FuncContextError(ctx, e) => {
                 write!(
@@ -106,22 +252,67 @@ impl Display for LinkError {
             LinkError::DataIndexOverflowError => {
                 write!(f, "All of the instruction data takes more than 4 bytes to index. The maximum instruction operand width is 4 bytes. Try to reduce file size and try again.")
             }
-            LinkError::MissingEntryPointError(entry_point) => {
+            LinkError::MissingEntryPointError(entry_point, suggestion) => {
                 write!(
                     f,
                     "Cannot create executable, missing entry point: {}.",
                     entry_point
+                )?;
+
+                match suggestion {
+                    Some(name) => write!(f, " Did you mean {}?", name),
+                    None => Ok(()),
+                }
+            }
+            LinkError::LocalEntryPointError(entry_point, file_name) => {
+                write!(
+                    f,
+                    "Cannot create executable, entry point {} is defined as local in {}; make it global",
+                    entry_point, file_name
                 )
             }
             LinkError::MissingInitFunctionError => {
                 write!(f, "Cannot create shared object, missing _init function.")
             }
+            LinkError::HiddenInitFunctionError => {
+                write!(
+                    f,
+                    "Cannot create shared object, _init must be exported and cannot be hidden"
+                )
+            }
+            LinkError::InvalidEntryOffsetError(entry_offset, instruction_count) => {
+                write!(
+                    f,
+                    "Link error: Entry offset {} is past the end of the entry point, which has {} instructions",
+                    entry_offset, instruction_count
+                )
+            }
             LinkError::EntryInSharedError => {
                 write!(
                     f,
                     "Cannot create shared object, _start or other entry point is present"
                 )
             }
+            LinkError::InitEntryPointError => {
+                write!(
+                    f,
+                    "Cannot use _init as the entry point, it is reserved for the initialization routine that runs before the entry point"
+                )
+            }
+            LinkError::CommentSourceNotFoundError(path) => {
+                write!(
+                    f,
+                    "Cannot take the output's comment from {}, it is not one of the input files",
+                    path
+                )
+            }
+            LinkError::InitCycleError(cycle) => {
+                write!(
+                    f,
+                    "Cannot create shared object, _init depends on itself: {}",
+                    cycle.join(" -> ")
+                )
+            }
             LinkError::UnresolvedExternalSymbolError(name) => {
                 write!(
                     f,
@@ -129,11 +320,73 @@ impl Display for LinkError {
                     name
                 )
             }
-            LinkError::InvalidSymbolRefError(name, instr_index, sym_hash) => {
+            LinkError::LocalDefinitionError(name, file_name) => {
+                write!(
+                    f,
+                    "Unresolved external symbol error. Symbol \"{}\" is defined as local in {} and cannot satisfy an external reference; make it global",
+                    name, file_name
+                )
+            }
+            LinkError::MissingWrapSymbolError(name) => {
                 write!(
                     f,
-                    "Error in {}:\nInstruction at index {} references invalid symbol, hash: {}",
-                    name, instr_index, sym_hash
+                    "Cannot wrap \"{0}\", no input defines a global \"__wrap_{0}\"",
+                    name
+                )
+            }
+            LinkError::MissingKeepSymbolError(name) => {
+                write!(
+                    f,
+                    "Cannot keep \"{}\", no input defines a global function with that name",
+                    name
+                )
+            }
+            LinkError::InvalidSymbolRefError(ctx, instr_index, symbol_name) => {
+                write!(
+                    f,
+                    "Error linking {}, in function {} ({}):\nInstruction {} references undefined symbol '{}'",
+                    ctx.file_context.input_file_name,
+                    ctx.func_name,
+                    ctx.file_context.source_file_name,
+                    instr_index,
+                    symbol_name
+                )
+            }
+            LinkError::WarningsAsErrors(warnings) => {
+                write!(
+                    f,
+                    "Link error: {} warning(s) treated as errors because of --werror:",
+                    warnings.len()
+                )?;
+
+                // These aren't also printed as warnings, so this is where they are shown
+                for warning in warnings {
+                    write!(f, "\n{}", warning)?;
+                }
+
+                Ok(())
+            }
+            LinkError::MergeError(message) => {
+                write!(f, "Cannot merge KSM files, {}", message)
+            }
+            LinkError::MergeEntryPointError => {
+                write!(
+                    f,
+                    "Cannot merge KSM files, both the base and the add-on have code in their main section, which is what runs when a file is loaded. Link the add-on with --split-sections if it only has functions and _init"
+                )
+            }
+            LinkError::ValidationError(message) => {
+                write!(
+                    f,
+                    "Internal linker error, the output failed validation: {}. Please report this as a bug",
+                    message
+                )
+            }
+            LinkError::WorkerPanic(file_name, message) => {
+                write!(
+                    f,
+                    "Link error: Internal error while processing {}: {}",
+                    file_name, message
                 )
             }
         }
@@ -146,18 +399,18 @@ impl Display for ProcessingError {
             ProcessingError::MissingNameError(object) => {
                 write!(f, "{} is missing a name entry", object)
             }
-            ProcessingError::InvalidDataIndexError(instr_index, data_index) => {
+            ProcessingError::InvalidDataIndexError(instr_index, operand_index, data_index) => {
                 write!(
                     f,
-                    "Instruction number {} has invalid data index {}",
-                    instr_index, data_index
+                    "Instruction number {}, operand {} references invalid data index {}",
+                    instr_index, operand_index, data_index
                 )
             }
-            ProcessingError::InvalidSymbolIndexError(instr_index, symbol_index) => {
+            ProcessingError::InvalidSymbolIndexError(instr_index, operand_index, symbol_index) => {
                 write!(
                     f,
-                    "Instruction number {} has invalid symbol index {}",
-                    instr_index, symbol_index
+                    "Instruction number {}, operand {} references invalid symbol index {}",
+                    instr_index, operand_index, symbol_index
                 )
             }
             ProcessingError::MissingSymbolNameError(symbol_index, name_index) => {
@@ -174,15 +427,65 @@ impl Display for ProcessingError {
                     symbol_name, value_index
                 )
             }
-            ProcessingError::DuplicateSymbolError(symbol_name, original_file) => {
+            ProcessingError::DuplicateSymbolError(
+                symbol_name,
+                original_type,
+                duplicate_type,
+                original,
+                duplicate,
+            ) => {
+                if symbol_kind(*original_type) == symbol_kind(*duplicate_type) {
+                    write!(
+                        f,
+                        "Duplicate {} '{}', defined in {} and again in {}",
+                        symbol_kind(*original_type),
+                        symbol_name,
+                        original,
+                        duplicate
+                    )
+                } else {
+                    write!(
+                        f,
+                        "Duplicate symbol '{}', defined as a {} in {} and again as a {} in {}",
+                        symbol_name,
+                        symbol_kind(*original_type),
+                        original,
+                        symbol_kind(*duplicate_type),
+                        duplicate
+                    )
+                }
+            }
+            ProcessingError::InvalidRelocationOperandError(instr_index, operand_index) => {
                 write!(
                     f,
-                    "Multiple definitions of '{}', first defined in {}",
-                    symbol_name, original_file
+                    "Relocation entry for instruction number {} references operand {}, which the instruction does not have",
+                    instr_index, operand_index
                 )
             }
-            ProcessingError::FuncMissingSymbolError => {
-                write!(f, "Function missing associated symbol table entry")
+            ProcessingError::InvalidRelocationInstrError(instr_index, instr_count) => {
+                write!(
+                    f,
+                    "Relocation entry references instruction number {}, but the function only has {} instructions",
+                    instr_index, instr_count
+                )
+            }
+            ProcessingError::OpcodeArityMismatchError(instr_index, opcode, got, expected) => {
+                write!(
+                    f,
+                    "Instruction number {} is {} with {} operand(s), but {} takes {}",
+                    instr_index,
+                    <&str>::from(*opcode),
+                    got,
+                    <&str>::from(*opcode),
+                    expected
+                )
+            }
+            ProcessingError::FuncMissingSymbolError(section_name, section_index) => {
+                write!(
+                    f,
+                    "Function section {} (section index {}) has no symbol named \"{}\" in .symtab. Every function section needs a symbol of type Func with the same name, this is likely a bug in the assembler that created this file",
+                    section_name, section_index, section_name
+                )
             }
             ProcessingError::FuncSymbolInvalidTypeError => {
                 write!(f, "Function symbol has invalid type, a symbol entry with the same name as a function must be of SymType::Func")
@@ -191,6 +494,61 @@ impl Display for ProcessingError {
     }
 }
 
+impl Display for LinkWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkWarning::EmptyEntryPointWarning(entry_point) => {
+                write!(
+                    f,
+                    "Warning: entry point {} has no instructions, the program will exit immediately",
+                    entry_point
+                )
+            }
+            LinkWarning::ArgIndexWidthWarning(index_bytes, arg_section_size) => {
+                // The largest argument section that can still be indexed with one byte fewer
+                let threshold = (1usize << (8 * (index_bytes - 1))) - 1;
+
+                write!(
+                    f,
+                    "Warning: argument section is {} bytes, past the {} byte limit for {} byte operands, so every operand now takes {} bytes",
+                    arg_section_size,
+                    threshold,
+                    index_bytes - 1,
+                    index_bytes
+                )
+            }
+            LinkWarning::DuplicateInputWarning(input_name) => {
+                write!(
+                    f,
+                    "Warning: input {} was given more than once, only the first is linked",
+                    input_name
+                )
+            }
+            LinkWarning::MultipleDefinitionWarning(symbol_name, original, replacement) => {
+                write!(
+                    f,
+                    "Warning: '{}' is defined in {} and again in {}, using the definition in {}",
+                    symbol_name, original, replacement, replacement.source_file_name
+                )
+            }
+            LinkWarning::BuiltinShadowWarning(func_name, file_name) => {
+                write!(
+                    f,
+                    "Warning: global function '{}' in {} has the same name as a kOS built-in function, and will be called in its place",
+                    func_name, file_name
+                )
+            }
+            LinkWarning::LayoutFunctionWarning(func_name) => {
+                write!(
+                    f,
+                    "Warning: function '{}' is in the layout file, but is not in the output",
+                    func_name
+                )
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FileErrorContext {
     pub input_file_name: String,
@@ -202,3 +560,29 @@ pub struct FuncErrorContext {
     pub file_context: FileErrorContext,
     pub func_name: String,
 }
+
+/// Where a symbol was defined, including the function it belongs to if it is known
+#[derive(Debug, Clone)]
+pub struct DefinitionContext {
+    pub source_file_name: String,
+    pub func_name: Option<String>,
+}
+
+impl Display for DefinitionContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.func_name {
+            Some(func_name) => write!(f, "{} function {}", self.source_file_name, func_name),
+            None => write!(f, "{}", self.source_file_name),
+        }
+    }
+}
+
+/// What a symbol of this type is called in error messages
+fn symbol_kind(sym_type: SymType) -> &'static str {
+    match sym_type {
+        SymType::Func => "function",
+        SymType::NoType | SymType::Object => "variable",
+        SymType::Section => "section",
+        SymType::File => "file",
+    }
+}
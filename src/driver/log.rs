@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+use std::fmt::{self, Arguments, Display, Formatter};
+use std::hash::Hasher;
+use std::time::{Duration, Instant};
+
+use crate::tables::NameHasher;
+
+/// How verbose the linker has to be told to be before a message is shown, from -v to -vvv
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// High-level information about each phase of the link
+    Phases = 1,
+    /// Information about each input file
+    Files = 2,
+    /// Everything, including dumps of the instructions being linked
+    Dumps = 3,
+}
+
+impl LogLevel {
+    /// The verbosity that shows every message, which --debug is an alias for
+    pub const MAX: u8 = LogLevel::Dumps as u8;
+}
+
+/// Prints a message if the verbosity is at least the given level.
+///
+/// Messages go to stderr, so that they never end up mixed into an output written to stdout.
+pub fn log(verbosity: u8, level: LogLevel, message: Arguments) {
+    if verbosity >= level as u8 {
+        eprintln!("{}", message);
+    }
+}
+
+/// Prints everything the linker has to say other than errors: warnings, what -v logs, and the
+/// reports asked for with options like --print-symbols. With --quiet, none of it is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reporter {
+    verbosity: u8,
+    quiet: bool,
+    to_stderr: bool,
+}
+
+impl Reporter {
+    /// Reports go to stderr instead of stdout if `to_stderr` is set, such as when the output is
+    /// being written to stdout
+    pub fn new(verbosity: u8, quiet: bool, to_stderr: bool) -> Self {
+        Reporter {
+            verbosity,
+            quiet,
+            to_stderr,
+        }
+    }
+
+    /// If nothing is printed at all
+    pub fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// Prints a message if the verbosity is at least the given level, see log
+    pub fn log(&self, level: LogLevel, message: Arguments) {
+        if !self.quiet {
+            log(self.verbosity, level, message);
+        }
+    }
+
+    /// Prints a warning, which always goes to stderr
+    pub fn warning(&self, warning: impl Display) {
+        if !self.quiet {
+            eprintln!("{}", warning);
+        }
+    }
+
+    /// Prints a line of a report
+    pub fn report(&self, line: impl Display) {
+        if self.quiet {
+            return;
+        }
+
+        if self.to_stderr {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Measures how long each phase of a link takes, one after another
+pub struct PhaseTimer {
+    phases: Vec<(&'static str, Duration)>,
+    start: Instant,
+}
+
+impl PhaseTimer {
+    /// Starts timing the first phase
+    pub fn start() -> Self {
+        PhaseTimer {
+            phases: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Records the phase that was running under the given name, and starts timing the next one
+    pub fn finish(&mut self, name: &'static str) {
+        let now = Instant::now();
+
+        self.phases.push((name, now - self.start));
+        self.start = now;
+    }
+
+    /// The recorded phases, in the order they finished
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+}
+
+impl Display for PhaseTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, (name, duration)) in self.phases.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{}: {}ms", name, duration.as_millis())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reports each time a symbol asked for with --trace-symbol is seen while resolving symbols
+pub struct SymbolTrace {
+    hashes: HashSet<u64>,
+    reporter: Reporter,
+    lines: Vec<String>,
+}
+
+impl SymbolTrace {
+    /// Traces the given symbol names, printing each line with the reporter
+    pub fn new(names: &[String], reporter: Reporter) -> Self {
+        let hashes = names
+            .iter()
+            .map(|name| {
+                let mut hasher = NameHasher::default();
+                hasher.write(name.as_bytes());
+                hasher.finish()
+            })
+            .collect();
+
+        SymbolTrace {
+            hashes,
+            reporter,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Prints and records a line if the symbol with this name hash is being traced. The line is
+    /// only created if it will be used.
+    pub fn record(&mut self, name_hash: u64, line: impl FnOnce() -> String) {
+        if !self.hashes.contains(&name_hash) {
+            return;
+        }
+
+        let line = line();
+
+        self.reporter.report(&line);
+
+        self.lines.push(line);
+    }
+
+    /// Every line that has been recorded, in order
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+}
@@ -0,0 +1,187 @@
+use kerbalobjects::ksm::sections::{ArgIndex, ArgumentSection, CodeSection, CodeType};
+use kerbalobjects::ksm::{Instr, KSMFile, KSMFileBuilder};
+use kerbalobjects::{KOSValue, Opcode};
+
+use super::errors::{LinkError, LinkResult};
+use super::validate::{self, is_label_operand, label_target};
+
+/// The order the linker writes code sections in, which the merged file keeps
+const SECTION_ORDER: [CodeType; 3] = [CodeType::Function, CodeType::Initialization, CodeType::Main];
+
+/// Combines two files that have already been linked into one, with the add-on's code placed after
+/// the base's in each code section.
+///
+/// Labels are the index of the instruction they point to, so every label that is jumped or called
+/// to in both files is moved to wherever its instruction ends up, and every operand is added to the
+/// new argument section. Any other value is copied as it is, even if it looks like a label.
+/// Both files have to be laid out the way this linker writes them, with a single label reset to
+/// @0001 as the very first instruction.
+///
+/// If both files have code in their main section, which is where kOS begins running a file, the
+/// merge fails unless `keep_both_entries` is set. Then the base's main code runs, and the add-on's
+/// is placed after it.
+///
+/// The base's comment and debug section are kept, the add-on's are not.
+pub fn merge(base: KSMFile, addon: KSMFile, keep_both_entries: bool) -> LinkResult<KSMFile> {
+    let files = [
+        ("base", &base, instructions("base", &base)?),
+        ("add-on", &addon, instructions("add-on", &addon)?),
+    ];
+
+    let section_lengths = files.each_ref().map(|(_, _, instrs)| {
+        SECTION_ORDER.map(|section_type| {
+            instrs
+                .iter()
+                .filter(|(instr_type, _)| *instr_type == section_type)
+                .count()
+        })
+    });
+
+    // kOS runs the main section when a file is loaded, so code there is the file's entry point.
+    // Without --split-sections that is all of its code, even in a shared object.
+    let main = section_position(CodeType::Main);
+
+    if !keep_both_entries && section_lengths[0][main] > 0 && section_lengths[1][main] > 0 {
+        return Err(LinkError::MergeEntryPointError);
+    }
+
+    // Each section holds all of the base's instructions of that type followed by the add-on's, and
+    // the label reset before everything has no label of its own
+    let mut next_labels = [[0; 3]; 2];
+    let mut section_start = 1;
+
+    for i in 0..SECTION_ORDER.len() {
+        next_labels[0][i] = section_start;
+        next_labels[1][i] = section_start + section_lengths[0][i];
+        section_start += section_lengths[0][i] + section_lengths[1][i];
+    }
+
+    let new_labels = [0, 1].map(|file_index| {
+        let mut labels = vec![None];
+
+        for (instr_type, _) in files[file_index].2.iter() {
+            let next_label = &mut next_labels[file_index][section_position(*instr_type)];
+
+            labels.push(Some(*next_label));
+            *next_label += 1;
+        }
+
+        labels
+    });
+
+    let mut arg_section = ArgumentSection::new();
+
+    // The linker puts the comment before anything else
+    if let Some(comment @ KOSValue::String(_)) = base.arg_section.arguments().next() {
+        arg_section.add(comment.clone());
+    }
+
+    let begin_index = arg_section.add_checked(KOSValue::String(String::from("@0001")));
+
+    let mut sections = SECTION_ORDER.map(CodeSection::new);
+
+    sections[0].add(Instr::OneOp(Opcode::Lbrt, begin_index));
+
+    for (section_index, section_type) in SECTION_ORDER.into_iter().enumerate() {
+        for ((name, ksm_file, instrs), labels) in files.iter().zip(new_labels.iter()) {
+            // The first instruction is the label reset, which isn't in the list
+            for (old_index, (_, instr)) in instrs
+                .iter()
+                .enumerate()
+                .map(|(i, instr)| (i + 1, instr))
+                .filter(|(_, (instr_type, _))| *instr_type == section_type)
+            {
+                let mut rebase = |op: ArgIndex, operand_index: usize| -> LinkResult<ArgIndex> {
+                    let value = ksm_file.arg_section.get(op).ok_or_else(|| {
+                        LinkError::MergeError(format!(
+                            "instruction {} of the {} file refers to argument index {}, which is not in its argument section",
+                            old_index,
+                            name,
+                            usize::from(op)
+                        ))
+                    })?;
+
+                    // Values that only look like labels are data, and are copied as they are
+                    let label = if is_label_operand(instr.opcode(), operand_index) {
+                        label_target(value)
+                    } else {
+                        None
+                    };
+
+                    let value = match label {
+                        Some(label) => {
+                            let new_label =
+                                labels.get(label).copied().flatten().ok_or_else(|| {
+                                    LinkError::MergeError(format!(
+                                        "instruction {} of the {} file refers to label {}, which is not an instruction in it",
+                                        old_index, name, validate::label(label)
+                                    ))
+                                })?;
+
+                            KOSValue::String(validate::label(new_label))
+                        }
+                        None => value.clone(),
+                    };
+
+                    Ok(arg_section.add_checked(value))
+                };
+
+                let new_instr = match instr {
+                    Instr::ZeroOp(opcode) => Instr::ZeroOp(*opcode),
+                    Instr::OneOp(opcode, op) => Instr::OneOp(*opcode, rebase(*op, 1)?),
+                    Instr::TwoOp(opcode, op1, op2) => {
+                        Instr::TwoOp(*opcode, rebase(*op1, 1)?, rebase(*op2, 2)?)
+                    }
+                };
+
+                sections[section_index].add(new_instr);
+            }
+        }
+    }
+
+    let builder = KSMFileBuilder::new()
+        .with_arg_section(arg_section)
+        .with_code_sections(Vec::from(sections));
+
+    Ok(builder.with_debug_section(base.debug_section).finish())
+}
+
+/// Every instruction in a linked file after the label reset that begins it, in the order kOS
+/// numbers them, along with the type of section each is in
+fn instructions<'a>(name: &str, ksm_file: &'a KSMFile) -> LinkResult<Vec<(CodeType, &'a Instr)>> {
+    let mut instrs = ksm_file.code_sections().flat_map(|code_section| {
+        code_section
+            .instructions()
+            .map(|instr| (code_section.section_type, instr))
+    });
+
+    let begin_label = KOSValue::String(String::from("@0001"));
+
+    let starts_at_one = matches!(
+        instrs.next(),
+        Some((_, Instr::OneOp(Opcode::Lbrt, op))) if ksm_file.arg_section.get(*op) == Some(&begin_label)
+    );
+
+    let instrs: Vec<(CodeType, &Instr)> = instrs.collect();
+
+    if !starts_at_one
+        || instrs
+            .iter()
+            .any(|(_, instr)| instr.opcode() == Opcode::Lbrt)
+    {
+        return Err(LinkError::MergeError(format!(
+            "the {} file doesn't start with a single label reset to @0001, so its labels can't be moved",
+            name
+        )));
+    }
+
+    Ok(instrs)
+}
+
+/// Where a section type is in the order sections are written
+fn section_position(section_type: CodeType) -> usize {
+    SECTION_ORDER
+        .iter()
+        .position(|order_type| *order_type == section_type)
+        .expect("Every code section type has a place in the order")
+}
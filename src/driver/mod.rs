@@ -1,37 +1,71 @@
-use crate::driver::errors::{LinkError, ProcessingError};
+use crate::driver::errors::{LinkError, LinkWarning, ProcessingError};
 use crate::tables::{
-    ContextHash, DataTable, Function, MasterSymbolEntry, NameTable, NameTableEntry, ObjectData,
-    SymbolTable, TempInstr, TempOperand,
+    ContextHash, DataTable, Function, MasterSymbolEntry, NameHasher, NameTable, NameTableEntry,
+    ObjectData, SymbolEntry, SymbolTable, TempInstr, TempOperand,
 };
 use crate::CLIConfig;
+use debug::SourceMapEntry;
 use errors::LinkResult;
 use kerbalobjects::ko::sections::DataIdx;
 use kerbalobjects::ko::symbols::{SymBind, SymType};
 use kerbalobjects::ko::KOFile;
 use kerbalobjects::ksm::sections::{
-    ArgIndex, ArgumentSection, CodeSection, DebugEntry, DebugRange, DebugSection,
+    ArgIndex, ArgumentSection, CodeSection, CodeType, DebugRange, DebugSection,
 };
 use kerbalobjects::ksm::KSMFile;
 use kerbalobjects::ksm::{Instr, KSMFileBuilder};
 use kerbalobjects::{KOSValue, Opcode};
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsString;
 use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read};
 use std::num::NonZeroUsize;
-use std::panic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::slice::Iter;
 use std::thread::{self, JoinHandle};
 
+pub mod archive;
+pub mod builtins;
+pub mod cache;
+pub mod debug;
+pub mod log;
+pub mod merge;
 pub mod reader;
+pub mod summary;
+pub mod validate;
+use cache::{FileStamp, ObjectCache};
+use log::{LogLevel, PhaseTimer, SymbolTrace};
 use reader::Reader;
+use summary::{FunctionSummary, InputSummary, LinkSummary, SectionSizes, ValueTypeStats};
 
-use self::errors::{FileErrorContext, FuncErrorContext};
+use self::errors::{DefinitionContext, FileErrorContext, FuncErrorContext};
 
 pub mod errors;
 
+/// A function's name hash, if it is global, and the index of the file it is in
+type FuncRef = (u64, bool, usize);
+
 pub struct Driver {
     config: CLIConfig,
-    thread_handles: Vec<JoinHandle<LinkResult<ObjectData>>>,
+    // Each handle is paired with the name of the input it is processing, in case the thread panics
+    thread_handles: Vec<(String, JoinHandle<LinkResult<ObjectData>>)>,
+    archive_handles: Vec<(String, JoinHandle<LinkResult<Vec<ObjectData>>>)>,
+    summary: Option<LinkSummary>,
+    exports: Vec<String>,
+    function_offsets: HashMap<String, usize>,
+    warnings: Vec<LinkWarning>,
+    cache: Option<ObjectCache>,
+    // The canonicalized paths of every input added by path, to catch the same file given twice
+    input_paths: HashSet<PathBuf>,
+    duplicate_inputs: Vec<String>,
+    // Everything that --trace-symbol reported during the last link
+    symbol_trace: Vec<String>,
+    source_map: Vec<SourceMapEntry>,
+    // The tree that --gc-sections-print-graph printed during the last link
+    gc_graph: Vec<String>,
+    // Every global symbol that --print-exports printed during the last link
+    export_report: Vec<String>,
 }
 
 impl Driver {
@@ -39,38 +73,350 @@ impl Driver {
         Driver {
             config,
             thread_handles: Vec::with_capacity(16),
+            archive_handles: Vec::new(),
+            summary: None,
+            exports: Vec::new(),
+            function_offsets: HashMap::new(),
+            warnings: Vec::new(),
+            cache: None,
+            input_paths: HashSet::new(),
+            duplicate_inputs: Vec::new(),
+            symbol_trace: Vec::new(),
+            source_map: Vec::new(),
+            gc_graph: Vec::new(),
+            export_report: Vec::new(),
+        }
+    }
+
+    /// Creates a driver that reuses processed input files from the cache if they haven't changed
+    /// since they were cached, and caches any that are processed. Only files added by path with
+    /// Driver::add are cached.
+    pub fn with_cache(config: CLIConfig, cache: ObjectCache) -> Self {
+        Driver {
+            cache: Some(cache),
+            ..Driver::new(config)
         }
     }
 
     pub fn add(&mut self, path: impl Into<PathBuf>) {
         let path = path.into();
+        let input_name = path.to_string_lossy().into_owned();
+        let resolve_direct_symrefs = self.config.resolve_direct_symrefs;
+        let require_reld = self.config.require_reld;
+
+        let path = match self.find_input(path) {
+            Ok(path) => path,
+            Err(error) => {
+                self.thread_handles
+                    .push((input_name, thread::spawn(move || Err(error))));
+                return;
+            }
+        };
+
+        if self.is_duplicate_input(&path) {
+            self.duplicate_inputs.push(input_name);
+            return;
+        }
+
+        let cache = self.cache.clone();
 
         let handle = thread::spawn(move || {
-            let (file_name, kofile) = Reader::read_file(path)?;
-            Reader::process_file(file_name, kofile)
+            // The stamp is taken before reading so that a change made while reading isn't missed
+            let stamp = cache.as_ref().and_then(|_| FileStamp::of(&path));
+
+            if let (Some(cache), Some(stamp)) = (&cache, stamp) {
+                if let Some(object_data) =
+                    cache.get(&path, stamp, resolve_direct_symrefs, require_reld)
+                {
+                    return Ok(object_data);
+                }
+            }
+
+            let (file_name, kofile) = Reader::read_file(&path)?;
+            let object_data =
+                Reader::process_file(file_name, kofile, resolve_direct_symrefs, require_reld)?;
+
+            if let (Some(cache), Some(stamp)) = (&cache, stamp) {
+                cache.insert(
+                    path,
+                    stamp,
+                    resolve_direct_symrefs,
+                    require_reld,
+                    object_data.clone(),
+                );
+            }
+
+            Ok(object_data)
         });
-        self.thread_handles.push(handle);
+        self.thread_handles.push((input_name, handle));
     }
 
     pub fn add_file(&mut self, file_name: String, kofile: KOFile) {
-        let handle = thread::spawn(move || Reader::process_file(file_name, kofile));
-        self.thread_handles.push(handle);
+        let input_name = file_name.to_owned();
+        let resolve_direct_symrefs = self.config.resolve_direct_symrefs;
+        let require_reld = self.config.require_reld;
+
+        let handle = thread::spawn(move || {
+            Reader::process_file(file_name, kofile, resolve_direct_symrefs, require_reld)
+        });
+        self.thread_handles.push((input_name, handle));
+    }
+
+    /// Adds a KO file that hasn't been parsed yet, such as one received over a network.
+    /// The file name is used in place of a path for error messages.
+    pub fn add_bytes(&mut self, file_name: String, bytes: Vec<u8>) {
+        self.add_reader(file_name, Box::new(Cursor::new(bytes)));
+    }
+
+    /// Adds a KO file that is read from anything that can be read from, such as a virtual file
+    /// system or an archive that is in memory. It is read on its own thread, like every other
+    /// input. The file name is used in place of a path for error messages.
+    pub fn add_reader(&mut self, file_name: String, reader: Box<dyn Read + Send>) {
+        let input_name = file_name.to_owned();
+        let resolve_direct_symrefs = self.config.resolve_direct_symrefs;
+        let require_reld = self.config.require_reld;
+
+        let handle = thread::spawn(move || {
+            let kofile = Reader::read_from(&file_name, reader)?;
+            Reader::process_file(file_name, kofile, resolve_direct_symrefs, require_reld)
+        });
+        self.thread_handles.push((input_name, handle));
+    }
+
+    /// Adds a KerbalObject archive. Its members are only linked if they define a symbol that
+    /// would otherwise be unresolved.
+    pub fn add_archive(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        let input_name = path.to_string_lossy().into_owned();
+        let resolve_direct_symrefs = self.config.resolve_direct_symrefs;
+        let require_reld = self.config.require_reld;
+
+        let path = match self.find_input(path) {
+            Ok(path) => path,
+            Err(error) => {
+                self.archive_handles
+                    .push((input_name, thread::spawn(move || Err(error))));
+                return;
+            }
+        };
+
+        if self.is_duplicate_input(&path) {
+            self.duplicate_inputs.push(input_name);
+            return;
+        }
+
+        let handle = thread::spawn(move || {
+            let (_, members) = Reader::read_archive(path)?;
+
+            members
+                .into_iter()
+                .map(|(member_name, kofile)| {
+                    Reader::process_file(member_name, kofile, resolve_direct_symrefs, require_reld)
+                })
+                .collect()
+        });
+        self.archive_handles.push((input_name, handle));
+    }
+
+    /// Finds where an input is. If it isn't at the path it was given, each --search-path directory
+    /// is tried in order. Absolute paths are never searched for.
+    fn find_input(&self, path: PathBuf) -> LinkResult<PathBuf> {
+        if self.config.search_paths.is_empty() || path.is_absolute() || path.exists() {
+            return Ok(path);
+        }
+
+        let mut searched = vec![path.to_string_lossy().into_owned()];
+
+        for dir in &self.config.search_paths {
+            let candidate = dir.join(&path);
+
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+
+            searched.push(candidate.to_string_lossy().into_owned());
+        }
+
+        Err(LinkError::InputNotFoundError(
+            path.to_string_lossy().into_owned(),
+            searched,
+        ))
+    }
+
+    /// Checks if this path has already been added, unless duplicate inputs are allowed. Paths that
+    /// can't be canonicalized are compared as given, and will fail to be read anyway.
+    fn is_duplicate_input(&mut self, path: &Path) -> bool {
+        if self.config.allow_duplicate_inputs {
+            return false;
+        }
+
+        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        !self.input_paths.insert(canonical_path)
+    }
+
+    /// Returns every warning that was emitted while linking
+    pub fn warnings(&self) -> Iter<'_, LinkWarning> {
+        self.warnings.iter()
+    }
+
+    /// Returns every line that --trace-symbol printed during the last link
+    pub fn symbol_trace(&self) -> &[String] {
+        &self.symbol_trace
+    }
+
+    /// Returns every line of the tree that --gc-sections-print-graph printed during the last link
+    pub fn gc_graph(&self) -> &[String] {
+        &self.gc_graph
+    }
+
+    /// Returns every line that --print-exports printed during the last link
+    pub fn export_report(&self) -> &[String] {
+        &self.export_report
+    }
+
+    /// Returns a summary of the last successful link, if there was one
+    pub fn summary(&self) -> Option<&LinkSummary> {
+        self.summary.as_ref()
+    }
+
+    /// Returns the names of every global function and data symbol in the output of the last
+    /// successful link, which are the symbols another program could import from it
+    pub fn exports(&self) -> Vec<String> {
+        self.exports.clone()
+    }
+
+    /// Returns the absolute instruction offset of every global function in the output of the last
+    /// successful link, by name. Local functions aren't included because their names aren't unique.
+    pub fn function_offsets(&self) -> HashMap<String, usize> {
+        self.function_offsets.clone()
+    }
+
+    /// Returns which function and source file each instruction in the output of the last successful
+    /// link came from, as one entry per function in the order they were written. Local functions
+    /// are included, and alignment padding isn't part of any function.
+    pub fn source_map(&self) -> &[SourceMapEntry] {
+        &self.source_map
+    }
+
+    /// Returns the type and instruction count of each code section in a linked file, in the
+    /// order that they are written
+    pub fn code_section_lengths(ksm_file: &KSMFile) -> Vec<(CodeType, usize)> {
+        ksm_file
+            .code_sections()
+            .map(|code_section| (code_section.section_type, code_section.instructions().len()))
+            .collect()
+    }
+
+    /// Resets the driver to how it was when it was created, so that it can link a different set
+    /// of inputs. The config and the cache, if there is one, are kept.
+    ///
+    /// Inputs that are still being read are waited for, so that no thread is left running on its
+    /// own. Whatever they return, even a panic, is thrown away.
+    pub fn clear(&mut self) {
+        for (_, handle) in self.thread_handles.drain(..) {
+            let _ = handle.join();
+        }
+
+        for (_, handle) in self.archive_handles.drain(..) {
+            let _ = handle.join();
+        }
+
+        self.summary = None;
+        self.exports.clear();
+        self.function_offsets.clear();
+        self.warnings.clear();
+        self.input_paths.clear();
+        self.duplicate_inputs.clear();
+        self.symbol_trace.clear();
+        self.source_map.clear();
+        self.gc_graph.clear();
+        self.export_report.clear();
     }
 
     pub fn link(&mut self) -> LinkResult<KSMFile> {
+        self.warnings.clear();
+        self.symbol_trace.clear();
+        self.gc_graph.clear();
+        self.export_report.clear();
+
+        self.input_paths.clear();
+        self.warnings.extend(
+            self.duplicate_inputs
+                .drain(..)
+                .map(LinkWarning::DuplicateInputWarning),
+        );
+
+        // Files are parsed on their own threads as they are added, so reading only counts the time
+        // spent waiting for those threads to finish
+        let mut timer = PhaseTimer::start();
+
         let mut object_data = Vec::with_capacity(self.thread_handles.len());
 
-        for handle in self.thread_handles.drain(..) {
+        for (input_name, handle) in self.thread_handles.drain(..) {
             let data = match handle.join() {
                 Ok(obj_data) => obj_data?,
-                Err(e) => panic::resume_unwind(e),
+                Err(payload) => {
+                    return Err(LinkError::WorkerPanic(
+                        input_name,
+                        Driver::panic_message(payload),
+                    ))
+                }
             };
 
+            self.config.reporter().log(
+                LogLevel::Files,
+                format_args!(
+                    "Read {}: {} function(s), {} symbol(s)",
+                    data.input_file_name,
+                    data.function_table.functions().len()
+                        + data.local_function_table.functions().len(),
+                    data.symbol_table.symbols().len() + data.local_symbol_table.symbols().len()
+                ),
+            );
+
             object_data.push(data);
         }
 
+        let mut archive_members = Vec::new();
+
+        for (input_name, handle) in self.archive_handles.drain(..) {
+            let members = match handle.join() {
+                Ok(members) => members?,
+                Err(payload) => {
+                    return Err(LinkError::WorkerPanic(
+                        input_name,
+                        Driver::panic_message(payload),
+                    ))
+                }
+            };
+
+            archive_members.extend(members);
+        }
+
+        Driver::pull_archive_members(&mut object_data, archive_members);
+
+        // Otherwise this would only be reported as a missing entry point
+        if object_data.is_empty() {
+            return Err(LinkError::NoInputFiles);
+        }
+
+        timer.finish("read");
+
+        let mut summary = LinkSummary {
+            entry_point: self.config.entry_point.to_owned(),
+            inputs: object_data
+                .iter()
+                .map(|data| InputSummary {
+                    file_name: data.input_file_name.to_owned(),
+                    source_file_name: data.source_file_name.to_owned(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+
         let init_hash = {
-            let mut hasher = DefaultHasher::new();
+            let mut hasher = NameHasher::default();
 
             hasher.write("_init".as_bytes());
 
@@ -78,11 +424,66 @@ impl Driver {
         };
 
         let entry_point_hash = {
-            let mut hasher = DefaultHasher::new();
+            let mut hasher = NameHasher::default();
             hasher.write(self.config.entry_point.as_bytes());
             hasher.finish()
         };
 
+        // _init is always run on its own before the entry point, so it can't be the entry point too
+        if !self.config.shared && entry_point_hash == init_hash {
+            return Err(LinkError::InitEntryPointError);
+        }
+
+        // A shared object has no entry point, so it takes its comment from the file with _init
+        let comment_func_hash = if self.config.shared {
+            init_hash
+        } else {
+            entry_point_hash
+        };
+
+        // Hiding only matters for shared and hybrid objects, since executables don't export anything
+        let hidden_symbol_hashes: HashSet<u64> = if self.config.shared || self.config.hybrid {
+            // A shared object is loaded by running _init, so it can't be hidden
+            if self.config.shared && self.config.hide_symbols.iter().any(|name| name == "_init") {
+                return Err(LinkError::HiddenInitFunctionError);
+            }
+
+            self.config
+                .hide_symbols
+                .iter()
+                .map(|name| {
+                    let mut hasher = NameHasher::default();
+                    hasher.write(name.as_bytes());
+                    hasher.finish()
+                })
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        // Each wrapped name maps to __wrap_NAME, and each __real_NAME maps back to the name
+        let wrapped_names: HashMap<u64, (u64, String)> = self
+            .config
+            .wrap_symbols
+            .iter()
+            .flat_map(|name| {
+                [
+                    (name.to_owned(), format!("__wrap_{}", name)),
+                    (format!("__real_{}", name), name.to_owned()),
+                ]
+            })
+            .map(|(name, target)| {
+                let mut hasher = NameHasher::default();
+                hasher.write(name.as_bytes());
+                let name_hash = hasher.finish();
+
+                let mut hasher = NameHasher::default();
+                hasher.write(target.as_bytes());
+
+                (name_hash, (hasher.finish(), target))
+            })
+            .collect();
+
         let mut master_data_table = DataTable::new();
         let mut master_symbol_table = NameTable::<MasterSymbolEntry>::new();
         let mut master_function_vec = Vec::new();
@@ -96,8 +497,10 @@ impl Driver {
 
         let builder = KSMFileBuilder::new();
         let mut arg_section = ArgumentSection::new();
-        // We only have one single code section that contains all executable instructions
-        let mut code_section = CodeSection::new(kerbalobjects::ksm::sections::CodeType::Main);
+        // Unless the sections are split, we only have one single code section that contains all executable instructions
+        let mut code_section = CodeSection::new(CodeType::Main);
+        let mut init_section = CodeSection::new(CodeType::Initialization);
+        let mut func_section = CodeSection::new(CodeType::Function);
 
         // Maps data hashes to arg section indexes
         let mut data_hash_map = HashMap::<u64, ArgIndex>::new();
@@ -107,14 +510,23 @@ impl Driver {
         let mut func_ref_vec: Vec<u64> = Vec::new();
         // Variable to keep track of the current absolute index of each function
         let mut func_offset = 0;
+        // The number of functions that were merged into an identical copy
+        let mut folded_count = 0;
+
+        let mut trace = SymbolTrace::new(&self.config.trace_symbols, self.config.reporter());
 
         // Resolve all symbols
-        for (object_data_index, data) in object_data.iter_mut().enumerate() {
-            let mut hasher = DefaultHasher::new();
+        for object_data_index in 0..object_data.len() {
+            // Symbols may clash with those in earlier files, which are needed to report errors
+            let (previous_object_data, remaining_object_data) =
+                object_data.split_at_mut(object_data_index);
+            let data = &mut remaining_object_data[0];
+
+            let mut hasher = NameHasher::default();
             hasher.write(data.input_file_name.as_bytes());
             let file_name_hash = ContextHash::FileNameHash(hasher.finish());
             let file_entry = NameTableEntry::from(data.input_file_name.to_owned(), ());
-            let file_name_index = file_name_table.insert(file_entry);
+            let (file_name_index, _) = file_name_table.insert(file_entry);
 
             // Add all function names
             for mut func_entry in data.function_name_table.drain() {
@@ -131,6 +543,8 @@ impl Driver {
                 func.set_object_data_index(object_data_index);
             }
 
+            Driver::wrap_references(data, &wrapped_names);
+
             // Resolve all symbols in this file
             Driver::resolve_symbols(
                 &mut master_symbol_table,
@@ -138,8 +552,16 @@ impl Driver {
                 &master_function_name_table,
                 file_name_hash,
                 data,
+                object_data_index,
+                previous_object_data,
                 &mut master_comment,
-                entry_point_hash,
+                comment_func_hash,
+                self.config.fold_identical,
+                self.config.allow_multiple_definition,
+                &mut folded_count,
+                &hidden_symbol_hashes,
+                &mut self.warnings,
+                &mut trace,
             )?;
 
             // Add all of the data in this file
@@ -148,15 +570,96 @@ impl Driver {
             }
         }
 
+        // Which definition each traced symbol ended up with
+        for name in self.config.trace_symbols.iter() {
+            let name_hash = {
+                let mut hasher = NameHasher::default();
+                hasher.write(name.as_bytes());
+                hasher.finish()
+            };
+
+            let Some(entry) = master_symbol_table.get_by_hash(name_hash) else {
+                continue;
+            };
+
+            trace.record(name_hash, || {
+                if entry.value().internal().sym_bind == SymBind::Extern {
+                    format!("{} is not defined by any input", name)
+                } else {
+                    format!(
+                        "{} resolved to the definition in {}",
+                        name,
+                        object_data[entry.value().object_data_index()].input_file_name
+                    )
+                }
+            });
+        }
+
+        self.symbol_trace = trace.into_lines();
+
+        // Printed before checking for external symbols, so that those are listed too
+        if self.config.print_symbols {
+            self.print_symbols(&master_symbol_table, &object_data);
+        }
+
+        // References were redirected to the wrappers, so one that is missing would only be
+        // reported as an unresolved __wrap_ symbol below
+        for name in self.config.wrap_symbols.iter() {
+            let wrapper_hash = {
+                let mut hasher = NameHasher::default();
+                hasher.write(format!("__wrap_{}", name).as_bytes());
+                hasher.finish()
+            };
+
+            let defined = master_symbol_table
+                .get_by_hash(wrapper_hash)
+                .is_some_and(|entry| entry.value().internal().sym_bind != SymBind::Extern);
+
+            if !defined {
+                return Err(LinkError::MissingWrapSymbolError(name.to_owned()));
+            }
+        }
+
         // At this point all of the symbols will have been resolved. Now we should check if there
         // are any external symbols left (bad!)
         for symbol_entry in master_symbol_table.entries() {
             if symbol_entry.value().internal().sym_bind == SymBind::Extern {
                 let name = symbol_entry.name().to_owned();
+
+                // Forgetting to make the definition global is a common mistake, so point it out
+                if let Some(file_name) = Driver::find_local_definition(&name, &object_data) {
+                    return Err(LinkError::LocalDefinitionError(name, file_name));
+                }
+
                 return Err(LinkError::UnresolvedExternalSymbolError(name));
             }
         }
 
+        // With --no-undefined, even external symbols that nothing uses have to be defined somewhere.
+        // Shared objects can still leave them to whatever loads them.
+        if self.config.no_undefined && !self.config.shared {
+            for data in object_data.iter() {
+                for name in data.unreferenced_externs.iter() {
+                    let defined = master_symbol_table
+                        .get(name)
+                        .is_some_and(|entry| entry.value().internal().sym_bind != SymBind::Extern);
+
+                    if !defined {
+                        if let Some(file_name) = Driver::find_local_definition(name, &object_data) {
+                            return Err(LinkError::LocalDefinitionError(
+                                name.to_owned(),
+                                file_name,
+                            ));
+                        }
+
+                        return Err(LinkError::UnresolvedExternalSymbolError(name.to_owned()));
+                    }
+                }
+            }
+        }
+
+        timer.finish("resolve_symbols");
+
         // Loop through all global functions
         for data in object_data.iter_mut() {
             for func in data.function_table.drain() {
@@ -186,6 +689,22 @@ impl Driver {
                 return Err(LinkError::EntryInSharedError);
             }
 
+            if self.config.entry_offset > 0
+                && self.config.entry_offset >= start_func.instruction_count()
+            {
+                return Err(LinkError::InvalidEntryOffsetError(
+                    self.config.entry_offset,
+                    start_func.instruction_count(),
+                ));
+            }
+
+            // An empty entry point just falls through and exits, which is almost always a mistake
+            if start_func.instruction_count() == 0 {
+                self.warnings.push(LinkWarning::EmptyEntryPointWarning(
+                    self.config.entry_point.to_owned(),
+                ));
+            }
+
             // _init should go before _start
             if init_function.is_some() {
                 temporary_function_vec.insert(1, start_func.clone());
@@ -197,14 +716,60 @@ impl Driver {
         } else {
             // If we are not a shared library, that is required
             if !self.config.shared {
+                // A local function can't be the entry point, even though it does exist
+                let local_entry_point = object_data.iter().find(|data| {
+                    data.local_function_name_table
+                        .get_by_hash(data.local_hash(&self.config.entry_point))
+                        .is_some()
+                });
+
+                if let Some(data) = local_entry_point {
+                    return Err(LinkError::LocalEntryPointError(
+                        self.config.entry_point.to_owned(),
+                        data.input_file_name.to_owned(),
+                    ));
+                }
+
+                // The casing is often different between the assembler and the link command
+                let mut similar_names = master_function_name_table
+                    .entries()
+                    .filter(|entry| entry.name().eq_ignore_ascii_case(&self.config.entry_point));
+
+                let suggestion = match (similar_names.next(), similar_names.next()) {
+                    (Some(entry), None) => Some(entry.name().to_owned()),
+                    _ => None,
+                };
+
                 return Err(LinkError::MissingEntryPointError(
                     self.config.entry_point.to_owned(),
+                    suggestion,
                 ));
             }
         }
 
+        // Shared objects are initialized by running _init, so _init must never end up calling itself
+        if self.config.shared {
+            if let Some(init_func) = &init_function {
+                if let Some(cycle) = Driver::find_init_cycle(
+                    init_func,
+                    &object_data,
+                    &master_symbol_table,
+                    &master_function_name_table,
+                    &temporary_function_vec,
+                ) {
+                    return Err(LinkError::InitCycleError(cycle));
+                }
+            }
+        }
+
+        // Every function that was reached from each root, and the function it was reached from
+        let mut reach_roots: Vec<FuncRef> = Vec::new();
+        let mut reach_edges: Vec<(FuncRef, FuncRef)> = Vec::new();
+
         // The two "root" functions for optimization are _init and _start
         if let Some(init_func) = &init_function {
+            reach_roots.push((init_func.name_hash(), true, init_func.object_data_index()));
+
             Driver::add_func_refs_optimize(
                 init_func.name_hash(),
                 true,
@@ -213,10 +778,13 @@ impl Driver {
                 &mut object_data,
                 &master_symbol_table,
                 &temporary_function_vec,
+                &mut reach_edges,
             );
         }
 
         if let Some(start_func) = &start_function {
+            reach_roots.push((start_func.name_hash(), true, start_func.object_data_index()));
+
             Driver::add_func_refs_optimize(
                 start_func.name_hash(),
                 true,
@@ -225,9 +793,150 @@ impl Driver {
                 &mut object_data,
                 &master_symbol_table,
                 &temporary_function_vec,
+                &mut reach_edges,
             );
         }
 
+        // Functions only reached in ways that can't be seen here, such as by kOS itself, are kept
+        // by name. A name that isn't a global function is most likely a typo.
+        let mut keep_hashes: HashSet<u64> = HashSet::new();
+
+        for name in self.config.keep_symbols.iter() {
+            let mut hasher = NameHasher::default();
+            hasher.write(name.as_bytes());
+            let name_hash = hasher.finish();
+
+            if !temporary_function_vec
+                .iter()
+                .any(|func| func.name_hash() == name_hash)
+            {
+                return Err(LinkError::MissingKeepSymbolError(name.to_owned()));
+            }
+
+            keep_hashes.insert(name_hash);
+        }
+
+        // Every global function from a --whole-archive file is also a root, referenced or not, and
+        // so is every one a hybrid object exports or that was given to --keep
+        let extra_roots: Vec<(u64, usize)> = temporary_function_vec
+            .iter()
+            .filter(|func| {
+                if self.config.hybrid && !hidden_symbol_hashes.contains(&func.name_hash()) {
+                    return true;
+                }
+
+                if keep_hashes.contains(&func.name_hash()) {
+                    return true;
+                }
+
+                let file_name = &object_data[func.object_data_index()].input_file_name;
+
+                self.config.whole_archive.iter().any(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name == file_name.as_str())
+                })
+            })
+            .map(|func| (func.name_hash(), func.object_data_index()))
+            .collect();
+
+        for (func_name_hash, object_data_index) in extra_roots {
+            if !func_ref_vec.contains(&func_name_hash) {
+                func_ref_vec.push(func_name_hash);
+                reach_roots.push((func_name_hash, true, object_data_index));
+
+                Driver::add_func_refs_optimize(
+                    func_name_hash,
+                    true,
+                    &mut func_ref_vec,
+                    object_data_index,
+                    &mut object_data,
+                    &master_symbol_table,
+                    &temporary_function_vec,
+                    &mut reach_edges,
+                );
+            }
+        }
+
+        if self.config.print_gc_graph {
+            self.gc_graph = Driver::gc_graph_lines(
+                &reach_roots,
+                &reach_edges,
+                &object_data,
+                &master_function_name_table,
+            );
+
+            let reporter = self.config.reporter();
+
+            for line in self.gc_graph.iter() {
+                reporter.report(line);
+            }
+        }
+
+        // Functions that were removed are no longer there to be imported
+        let global_symbols: Vec<&NameTableEntry<MasterSymbolEntry>> = master_symbol_table
+            .entries()
+            .filter(|entry| {
+                let symbol = entry.value().internal();
+
+                symbol.sym_bind == SymBind::Global
+                    && match symbol.sym_type {
+                        SymType::Func => {
+                            let mut hasher = NameHasher::default();
+                            hasher.write(entry.name().as_bytes());
+
+                            func_ref_vec.contains(&hasher.finish())
+                        }
+                        SymType::NoType => true,
+                        _ => false,
+                    }
+            })
+            .collect();
+
+        if self.config.print_exports {
+            self.export_report = Driver::export_report_lines(&global_symbols);
+
+            let reporter = self.config.reporter();
+
+            for line in self.export_report.iter() {
+                reporter.report(line);
+            }
+        }
+
+        // Hidden symbols are only for use inside of this output
+        let exports = global_symbols
+            .iter()
+            .filter(|entry| !entry.value().is_hidden())
+            .map(|entry| entry.name().to_owned())
+            .collect();
+
+        // Record everything that is about to be removed because it was never referenced
+        for func in temporary_function_vec
+            .iter()
+            .filter(|func| !func_ref_vec.contains(&func.name_hash()))
+        {
+            summary.eliminated_functions.push(Driver::function_summary(
+                func,
+                &object_data,
+                &master_function_name_table,
+                None,
+            ));
+        }
+
+        for data in object_data.iter() {
+            for func in data
+                .local_function_table
+                .functions()
+                .filter(|func| !data.local_function_ref_vec.contains(&func.name_hash()))
+            {
+                summary.eliminated_functions.push(Driver::function_summary(
+                    func,
+                    &object_data,
+                    &master_function_name_table,
+                    None,
+                ));
+            }
+        }
+
         // Now add all of the functions that are referenced
         for data in object_data.iter_mut() {
             for func in temporary_function_vec.drain(..) {
@@ -237,13 +946,128 @@ impl Driver {
                 }
             }
 
-            for func in data.local_function_table.drain() {
+            // Local functions are kept in the same order as they are in the source file
+            let mut local_funcs = data.local_function_table.drain();
+            local_funcs.sort_by_key(|func| func.section_index());
+
+            for func in local_funcs {
                 if data.local_function_ref_vec.contains(&func.name_hash()) {
                     master_function_vec.push(func);
                 }
             }
         }
 
+        // Identical local functions are emitted once, and every copy is pointed at that one
+        let mut folded_local_funcs = Vec::new();
+
+        if self.config.fold_identical {
+            let mut kept_funcs: Vec<Function> = Vec::with_capacity(master_function_vec.len());
+
+            for func in master_function_vec {
+                // Local hashes include the file they are from, so copies are matched by name
+                let identical = kept_funcs.iter().find(|kept| {
+                    !func.is_global()
+                        && !kept.is_global()
+                        && kept.object_data_index() != func.object_data_index()
+                        && Driver::function_name(kept, &object_data, &master_function_name_table)
+                            == Driver::function_name(
+                                &func,
+                                &object_data,
+                                &master_function_name_table,
+                            )
+                        && Driver::functions_identical(
+                            kept,
+                            &object_data[kept.object_data_index()].local_symbol_table,
+                            &func,
+                            &object_data[func.object_data_index()].local_symbol_table,
+                        )
+                });
+
+                match identical {
+                    Some(kept) => {
+                        folded_local_funcs.push((
+                            func.object_data_index(),
+                            func.name_hash(),
+                            kept.object_data_index(),
+                            kept.name_hash(),
+                        ));
+                    }
+                    None => kept_funcs.push(func),
+                }
+            }
+
+            folded_count += folded_local_funcs.len();
+            master_function_vec = kept_funcs;
+        }
+
+        self.config.reporter().log(
+            LogLevel::Phases,
+            format_args!("Folded {} identical function(s)", folded_count),
+        );
+
+        // Calls to tiny functions are replaced with their bodies before any offsets are found. The
+        // functions themselves are still kept, since finding out if anything else uses them would
+        // take another pass over everything.
+        if let Some(inline_threshold) = self.config.inline_threshold {
+            let inline_bodies: HashMap<u64, Vec<TempInstr>> = master_function_vec
+                .iter()
+                .filter(|func| {
+                    // A library's exports may be called from outside of it, so they are left alone
+                    let exported = (self.config.shared || self.config.hybrid)
+                        && func.is_global()
+                        && !hidden_symbol_hashes.contains(&func.name_hash());
+
+                    !exported
+                })
+                .filter_map(|func| {
+                    Driver::inline_body(func, inline_threshold, &master_data_table)
+                        .map(|body| (func.name_hash(), body))
+                })
+                .collect();
+
+            let mut inlined_count = 0;
+
+            for func in master_function_vec.iter_mut() {
+                inlined_count += Driver::inline_calls(
+                    func,
+                    &inline_bodies,
+                    &object_data[func.object_data_index()],
+                    &master_symbol_table,
+                );
+            }
+
+            self.config.reporter().log(
+                LogLevel::Phases,
+                format_args!("Inlined {} call(s)", inlined_count),
+            );
+        }
+
+        timer.finish("gc");
+
+        // The file that defines _init might not have a comment, in which case the first input
+        // with one describes the shared object instead
+        if self.config.shared && master_comment.is_none() {
+            master_comment = object_data.iter().find_map(|data| data.comment.clone());
+        }
+
+        // A comment given on the command line, or chosen from a specific file, replaces the entry
+        // point's comment
+        if let Some(comment) = &self.config.comment {
+            master_comment = Some(comment.to_owned());
+        } else if let Some(path) = &self.config.comment_from {
+            let data = object_data
+                .iter()
+                .find(|data| {
+                    path.file_name()
+                        .is_some_and(|name| name == data.input_file_name.as_str())
+                })
+                .ok_or_else(|| {
+                    LinkError::CommentSourceNotFoundError(path.to_string_lossy().into_owned())
+                })?;
+
+            master_comment = data.comment.clone();
+        }
+
         // Add in the comment if it exists
         if let Some(comment) = master_comment {
             let value = KOSValue::String(comment);
@@ -255,37 +1079,273 @@ impl Driver {
         // So we will add a `lbrt "@0001"` to make sure that the code begins correctly
         let begin_label = KOSValue::String(String::from("@0001"));
         let begin_index = arg_section.add(begin_label);
-        code_section.add(Instr::OneOp(Opcode::Lbrt, begin_index));
+        let begin_instr = Instr::OneOp(Opcode::Lbrt, begin_index);
+
+        // The function section is the first in the file, so the label has to be set there instead
+        if self.config.split_sections {
+            func_section.add(begin_instr);
+        } else {
+            code_section.add(begin_instr);
+        }
+
         func_offset += 1;
 
+        let section_type = |func: &Function| {
+            if !self.config.split_sections {
+                CodeType::Main
+            } else if func.is_global() && func.name_hash() == init_hash {
+                CodeType::Initialization
+            } else if func.is_global() && func.name_hash() == entry_point_hash {
+                CodeType::Main
+            } else {
+                CodeType::Function
+            }
+        };
+
+        let pinned = |func: &Function| {
+            if func.is_global() && func.name_hash() == init_hash {
+                0
+            } else if func.is_global() && func.name_hash() == entry_point_hash {
+                1
+            } else {
+                2
+            }
+        };
+
+        // _init has to run before anything else, and the entry point comes right after it, no
+        // matter which inputs they came from or how they were reached. The sort is stable, so
+        // everything else stays in the order it was found.
+        master_function_vec.sort_by_key(pinned);
+
+        // Sorting by name keeps the layout the same between builds whatever order the inputs are
+        // in, with _init and _start still first. Local functions with the same name are ordered by
+        // the file they are from.
+        if self.config.sort_functions {
+            master_function_vec.sort_by(|func, other_func| {
+                pinned(func)
+                    .cmp(&pinned(other_func))
+                    .then_with(|| {
+                        Driver::function_name(func, &object_data, &master_function_name_table).cmp(
+                            Driver::function_name(
+                                other_func,
+                                &object_data,
+                                &master_function_name_table,
+                            ),
+                        )
+                    })
+                    .then_with(|| {
+                        object_data[func.object_data_index()]
+                            .input_file_name
+                            .cmp(&object_data[other_func.object_data_index()].input_file_name)
+                    })
+            });
+        }
+
+        // Functions listed in the layout file come first, still after _init and the entry point. All
+        // of the functions with a listed name are moved, since local ones can share a name.
+        if let Some(layout_path) = &self.config.layout {
+            let layout = Driver::read_layout(layout_path)?;
+
+            for name in layout.iter() {
+                let retained = master_function_vec.iter().any(|func| {
+                    Driver::function_name(func, &object_data, &master_function_name_table) == name
+                });
+
+                if !retained {
+                    self.warnings
+                        .push(LinkWarning::LayoutFunctionWarning(name.to_owned()));
+                }
+            }
+
+            master_function_vec.sort_by_key(|func| {
+                let name = Driver::function_name(func, &object_data, &master_function_name_table);
+
+                (
+                    pinned(func),
+                    layout
+                        .iter()
+                        .position(|layout_name| layout_name == name)
+                        .unwrap_or(usize::MAX),
+                )
+            });
+        }
+
+        // Sections are written function, initialization, then main, so offsets must follow that order
+        master_function_vec.sort_by_key(|func| match section_type(func) {
+            CodeType::Function => 0,
+            CodeType::Initialization => 1,
+            _ => 2,
+        });
+
+        // Starting partway into the entry point needs a jump placed just before it
+        let entry_jump = self.config.entry_offset > 0;
+
+        let align = self.config.align_functions.map_or(1, NonZeroUsize::get);
+
+        // How many Nop instructions go before each function to align it
+        let mut func_padding = Vec::with_capacity(master_function_vec.len());
+
         // Loop through each function and find it's offset
         for func in master_function_vec.iter() {
-            func_offset = Driver::calc_func_offset(
+            if entry_jump && func.is_global() && func.name_hash() == entry_point_hash {
+                func_offset += 1;
+            }
+
+            let (padding, next_offset) = Driver::calc_func_offset(
                 func,
                 object_data.get_mut(func.object_data_index()).unwrap(),
                 &mut func_hash_map,
                 func_offset,
+                align,
             );
+
+            func_padding.push(padding);
+            func_offset = next_offset;
+        }
+
+        for (object_data_index, func_name_hash, kept_object_data_index, kept_name_hash) in
+            folded_local_funcs
+        {
+            let offset =
+                object_data[kept_object_data_index].local_function_hash_map[&kept_name_hash];
+
+            object_data[object_data_index]
+                .local_function_hash_map
+                .insert(func_name_hash, offset);
         }
 
+        let mut source_map = Vec::with_capacity(master_function_vec.len());
+
+        for func in master_function_vec.iter() {
+            let offset = if func.is_global() {
+                func_hash_map.get(&func.name_hash())
+            } else {
+                object_data[func.object_data_index()]
+                    .local_function_hash_map
+                    .get(&func.name_hash())
+            };
+
+            if let Some(offset) = offset {
+                source_map.push(SourceMapEntry {
+                    start: *offset,
+                    end: *offset + func.instruction_count(),
+                    func_name: Driver::function_name(
+                        func,
+                        &object_data,
+                        &master_function_name_table,
+                    )
+                    .to_owned(),
+                    source_file_name: object_data[func.object_data_index()]
+                        .source_file_name
+                        .to_owned(),
+                });
+            }
+
+            summary.retained_functions.push(Driver::function_summary(
+                func,
+                &object_data,
+                &master_function_name_table,
+                offset.copied(),
+            ));
+        }
+
+        if self.config.warn_builtins {
+            for func in summary
+                .retained_functions
+                .iter()
+                .filter(|func| func.is_global && builtins::is_builtin(&func.name))
+            {
+                self.warnings.push(LinkWarning::BuiltinShadowWarning(
+                    func.name.to_owned(),
+                    func.file_name.to_owned(),
+                ));
+            }
+        }
+
+        let function_offsets = func_hash_map
+            .iter()
+            .filter_map(|(hash, offset)| {
+                master_function_name_table
+                    .get_by_hash(*hash)
+                    .map(|entry| (entry.name().to_owned(), *offset))
+            })
+            .collect();
+
+        // References to the entry point, including the jump, go to the offset instead of its start
+        if let Some(entry_offset) = func_hash_map.get_mut(&entry_point_hash) {
+            *entry_offset += self.config.entry_offset;
+        }
+
+        timer.finish("offsets");
+
         // Now add the functions to the binary
-        for mut func in master_function_vec {
+        for (mut func, padding) in master_function_vec.into_iter().zip(func_padding) {
             let object_data_index = func.object_data_index();
+            let section = match section_type(&func) {
+                CodeType::Function => &mut func_section,
+                CodeType::Initialization => &mut init_section,
+                _ => &mut code_section,
+            };
+
+            if entry_jump && func.is_global() && func.name_hash() == entry_point_hash {
+                let entry_label =
+                    KOSValue::String(validate::label(func_hash_map[&entry_point_hash]));
+                let entry_label_index = arg_section.add(entry_label);
+
+                section.add(Instr::OneOp(Opcode::Jmp, entry_label_index));
+            }
+
             Driver::add_func_to_code_section(
                 &mut func,
                 &mut arg_section,
-                &mut code_section,
+                section,
                 &master_symbol_table,
                 &master_data_table,
                 &master_function_name_table,
                 &func_hash_map,
                 &mut data_hash_map,
                 object_data.get(object_data_index).unwrap(),
+                padding,
             )?;
         }
 
-        let init_section = CodeSection::new(kerbalobjects::ksm::sections::CodeType::Initialization);
-        let func_section = CodeSection::new(kerbalobjects::ksm::sections::CodeType::Function);
+        // Data is only added to the argument section when an instruction refers to it, so any
+        // value that only unreferenced functions used should be missing from it
+        let unemitted_data = master_data_table
+            .hashes()
+            .filter(|hash| !data_hash_map.contains_key(hash))
+            .count();
+
+        self.config.reporter().log(
+            LogLevel::Dumps,
+            format_args!(
+                "{} of {} data value(s) were never referenced and were left out of the argument section",
+                unemitted_data,
+                master_data_table.hashes().len()
+            ),
+        );
+
+        // Every operand grows by a byte each time the argument section crosses a threshold
+        let index_bytes = arg_section.num_index_bytes() as usize;
+
+        if self.config.warn_addr_width && index_bytes > 1 {
+            self.warnings.push(LinkWarning::ArgIndexWidthWarning(
+                index_bytes,
+                arg_section.size_bytes(),
+            ));
+        }
+
+        if let Some(addr_width) = self.config.addr_width {
+            if usize::from(addr_width) < index_bytes {
+                return Err(LinkError::AddrWidthTooSmallError(
+                    addr_width,
+                    index_bytes,
+                    arg_section.size_bytes(),
+                ));
+            }
+
+            Driver::pad_arg_section(&mut arg_section, addr_width);
+        }
 
         let builder = builder.with_arg_section(arg_section);
 
@@ -294,9 +1354,354 @@ impl Driver {
             .with_code_section(init_section)
             .with_code_section(code_section);
 
-        let debug_section = DebugSection::new(DebugEntry::new(1).with_range(DebugRange::new(2, 4)));
+        // kOS refuses to load a file whose debug section has no entries, so this placeholder entry is
+        // always written. KO files don't record source lines, so there is no real debug information
+        // to add yet.
+        let placeholder_entry = debug::debug_entry(1, [DebugRange::new(2, 4)])
+            .expect("Placeholder debug range is not empty");
+        let debug_section = DebugSection::new(placeholder_entry);
+
+        if self.config.werror && !self.warnings.is_empty() {
+            return Err(LinkError::WarningsAsErrors(
+                self.warnings
+                    .iter()
+                    .map(|warning| warning.to_string())
+                    .collect(),
+            ));
+        }
+
+        let ksm_file = builder.with_debug_section(debug_section).finish();
+
+        if self.config.validate {
+            validate::validate(&ksm_file)?;
+        }
+
+        timer.finish("emit");
+
+        self.config
+            .reporter()
+            .log(LogLevel::Phases, format_args!("{}", timer));
+
+        summary.sections = Driver::section_sizes(&ksm_file);
+        summary.argument_values = Driver::value_type_stats(&ksm_file.arg_section);
+
+        if self.config.print_stats {
+            let reporter = self.config.reporter();
+
+            for line in Driver::value_stats_lines(&summary.argument_values) {
+                reporter.report(line);
+            }
+        }
+
+        self.summary = Some(summary);
+        self.exports = exports;
+        self.function_offsets = function_offsets;
+        self.source_map = source_map;
+
+        Ok(ksm_file)
+    }
+
+    /// Panics almost always carry a &str or a String, anything else can't be described
+    fn panic_message(payload: Box<dyn Any + Send>) -> String {
+        match payload.downcast::<&str>() {
+            Ok(message) => (*message).to_owned(),
+            Err(payload) => match payload.downcast::<String>() {
+                Ok(message) => *message,
+                Err(_) => String::from("unknown panic"),
+            },
+        }
+    }
+
+    fn function_summary(
+        func: &Function,
+        object_data: &[ObjectData],
+        master_function_name_table: &NameTable<NonZeroUsize>,
+        offset: Option<usize>,
+    ) -> FunctionSummary {
+        let data = &object_data[func.object_data_index()];
+
+        FunctionSummary {
+            name: Driver::function_name(func, object_data, master_function_name_table).to_owned(),
+            file_name: data.input_file_name.to_owned(),
+            is_global: func.is_global(),
+            offset,
+        }
+    }
+
+    /// Labels each global symbol in the output as EXPORTED or HIDDEN, sorted by name
+    fn export_report_lines(global_symbols: &[&NameTableEntry<MasterSymbolEntry>]) -> Vec<String> {
+        let mut entries = global_symbols.to_vec();
+
+        entries.sort_by(|entry, other_entry| entry.name().cmp(other_entry.name()));
+
+        let name_width = entries
+            .iter()
+            .map(|entry| entry.name().len())
+            .max()
+            .unwrap_or(0);
+
+        entries
+            .iter()
+            .map(|entry| {
+                let visibility = if entry.value().is_hidden() {
+                    "HIDDEN"
+                } else {
+                    "EXPORTED"
+                };
+
+                format!("{:<name_width$} {}", entry.name(), visibility)
+            })
+            .collect()
+    }
+
+    /// Prints every symbol in the master symbol table sorted by name, along with its binding, type,
+    /// and the file it came from. This goes to stderr instead if the output is going to stdout.
+    fn print_symbols(
+        &self,
+        master_symbol_table: &NameTable<MasterSymbolEntry>,
+        object_data: &[ObjectData],
+    ) {
+        let mut entries: Vec<&NameTableEntry<MasterSymbolEntry>> =
+            master_symbol_table.entries().collect();
+
+        entries.sort_by(|entry, other_entry| entry.name().cmp(other_entry.name()));
+
+        let name_width = entries
+            .iter()
+            .map(|entry| entry.name().len())
+            .max()
+            .unwrap_or(0);
+
+        let reporter = self.config.reporter();
+
+        for entry in entries {
+            let symbol = entry.value().internal();
+            let file_name = object_data
+                .get(entry.value().object_data_index())
+                .map(|data| data.input_file_name.as_str())
+                .unwrap_or("<unknown>");
+
+            let line = format!(
+                "{:<name_width$} {:<6} {:<6} {}",
+                entry.name(),
+                format!("{:?}", symbol.sym_bind),
+                format!("{:?}", symbol.sym_type),
+                file_name,
+            );
+
+            reporter.report(line);
+        }
+    }
+
+    /// Finds the first input that defines a local function or symbol with the given name, which
+    /// can't be used to satisfy an external symbol
+    fn find_local_definition(name: &str, object_data: &[ObjectData]) -> Option<String> {
+        object_data
+            .iter()
+            .find(|data| {
+                let name_hash = data.local_hash(name);
+
+                data.local_function_name_table
+                    .get_by_hash(name_hash)
+                    .is_some()
+                    || data
+                        .local_symbol_table
+                        .get_by_hash(name_hash)
+                        .is_some_and(|entry| entry.internal().sym_bind == SymBind::Local)
+            })
+            .map(|data| data.input_file_name.to_owned())
+    }
+
+    /// Finds the name of a function, from the master table if it is global or from its own file's
+    /// table if it is local
+    fn function_name<'a>(
+        func: &Function,
+        object_data: &'a [ObjectData],
+        master_function_name_table: &'a NameTable<NonZeroUsize>,
+    ) -> &'a str {
+        let name_table = if func.is_global() {
+            master_function_name_table
+        } else {
+            &object_data[func.object_data_index()].local_function_name_table
+        };
+
+        name_table
+            .get_by_hash(func.name_hash())
+            .map(|entry| entry.name().as_str())
+            .unwrap_or_default()
+    }
+
+    fn section_sizes(ksm_file: &KSMFile) -> SectionSizes {
+        let index_bytes = ksm_file.arg_section.num_index_bytes();
+
+        let mut sizes = SectionSizes {
+            argument: ksm_file.arg_section.size_bytes(),
+            debug: ksm_file.debug_section.size_bytes(),
+            ..Default::default()
+        };
+
+        for code_section in ksm_file.code_sections() {
+            let size = code_section.size_bytes(index_bytes);
+
+            match code_section.section_type {
+                CodeType::Function => sizes.function += size,
+                CodeType::Initialization => sizes.initialization += size,
+                CodeType::Main => sizes.main += size,
+            }
+        }
+
+        sizes
+    }
+
+    /// Reads the function names in a layout file, one per line. Blank lines and lines starting with #
+    /// are ignored, the same as in response files.
+    fn read_layout(layout_path: &Path) -> LinkResult<Vec<String>> {
+        let contents = std::fs::read_to_string(layout_path)
+            .map_err(|e| LinkError::IOError(OsString::from(layout_path.as_os_str()), e.kind()))?;
+
+        Ok(contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Groups the values in an argument section by their type, sorted by the most bytes first
+    fn value_type_stats(arg_section: &ArgumentSection) -> Vec<ValueTypeStats> {
+        let mut stats: Vec<ValueTypeStats> = Vec::new();
+
+        for value in arg_section.arguments() {
+            let value_type = match value {
+                KOSValue::Null => "Null",
+                KOSValue::Bool(_) => "Bool",
+                KOSValue::Byte(_) => "Byte",
+                KOSValue::Int16(_) => "Int16",
+                KOSValue::Int32(_) => "Int32",
+                KOSValue::Float(_) => "Float",
+                KOSValue::Double(_) => "Double",
+                KOSValue::String(_) => "String",
+                KOSValue::ArgMarker => "ArgMarker",
+                KOSValue::ScalarInt(_) => "ScalarInt",
+                KOSValue::ScalarDouble(_) => "ScalarDouble",
+                KOSValue::BoolValue(_) => "BoolValue",
+                KOSValue::StringValue(_) => "StringValue",
+            };
+
+            match stats.iter_mut().find(|stat| stat.value_type == value_type) {
+                Some(stat) => {
+                    stat.count += 1;
+                    stat.bytes += value.size_bytes();
+                }
+                None => stats.push(ValueTypeStats {
+                    value_type: value_type.to_owned(),
+                    count: 1,
+                    bytes: value.size_bytes(),
+                }),
+            }
+        }
+
+        stats.sort_by(|stat, other_stat| {
+            other_stat
+                .bytes
+                .cmp(&stat.bytes)
+                .then_with(|| stat.value_type.cmp(&other_stat.value_type))
+        });
+
+        stats
+    }
+
+    /// One line for each type of value, with the share of the argument section's bytes it takes up
+    fn value_stats_lines(stats: &[ValueTypeStats]) -> Vec<String> {
+        let total_count: usize = stats.iter().map(|stat| stat.count).sum();
+        let total_bytes: usize = stats.iter().map(|stat| stat.bytes).sum();
+
+        let mut lines = vec![format!(
+            "Argument section: {} value(s), {} byte(s)",
+            total_count, total_bytes
+        )];
+
+        for stat in stats {
+            lines.push(format!(
+                "  {:<12} {:>6} value(s) {:>8} byte(s) {:>5.1}%",
+                stat.value_type,
+                stat.count,
+                stat.bytes,
+                stat.bytes as f64 * 100.0 / total_bytes as f64
+            ));
+        }
+
+        lines
+    }
+
+    /// Moves archive members into the list of linked objects, but only those that define a global
+    /// symbol that is still undefined. This repeats until no more members are pulled in, because
+    /// each new member may itself reference symbols defined by other members.
+    fn pull_archive_members(
+        object_data: &mut Vec<ObjectData>,
+        mut archive_members: Vec<ObjectData>,
+    ) {
+        let mut defined = HashSet::new();
+        let mut undefined = HashSet::new();
+
+        for data in object_data.iter() {
+            Driver::collect_symbol_hashes(data, &mut defined, &mut undefined);
+        }
+
+        loop {
+            undefined.retain(|hash| !defined.contains(hash));
+
+            let member_position = archive_members.iter().position(|member| {
+                member.symbol_table.symbols().any(|symbol| {
+                    symbol.internal().sym_bind == SymBind::Global
+                        && undefined.contains(&symbol.name_hash())
+                })
+            });
+
+            match member_position {
+                Some(position) => {
+                    let member = archive_members.remove(position);
+                    Driver::collect_symbol_hashes(&member, &mut defined, &mut undefined);
+                    object_data.push(member);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn collect_symbol_hashes(
+        data: &ObjectData,
+        defined: &mut HashSet<u64>,
+        undefined: &mut HashSet<u64>,
+    ) {
+        for symbol in data.symbol_table.symbols() {
+            match symbol.internal().sym_bind {
+                SymBind::Global => {
+                    defined.insert(symbol.name_hash());
+                }
+                SymBind::Extern => {
+                    undefined.insert(symbol.name_hash());
+                }
+                _ => {}
+            }
+        }
+    }
 
-        Ok(builder.with_debug_section(debug_section).finish())
+    /// Grows the argument section with unused values until operands take addr_width bytes.
+    ///
+    /// The operand width is always worked out from the argument section's size, so this is the
+    /// only way to make it wider. Nothing refers to the padding, and every existing index stays
+    /// the same because it is added at the end.
+    ///
+    /// The section has to reach the size that needs the width, so this costs about 64 KiB for a
+    /// width of 3 and about 1.6 MiB for 4, all of which kOS loads into memory.
+    fn pad_arg_section(arg_section: &mut ArgumentSection, addr_width: u8) {
+        // The longest string there can be, which keeps the number of values added down
+        let padding = KOSValue::String(" ".repeat(u8::MAX as usize));
+
+        while (arg_section.num_index_bytes() as u8) < addr_width {
+            arg_section.add(padding.clone());
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -310,7 +1715,13 @@ impl Driver {
         func_hash_map: &HashMap<u64, usize>,
         data_hash_map: &mut HashMap<u64, ArgIndex>,
         object_data: &ObjectData,
+        padding: usize,
     ) -> LinkResult<()> {
+        // Nop doesn't take an operand, so it can't change the argument section
+        for _ in 0..padding {
+            code_section.add(Instr::ZeroOp(Opcode::Nop));
+        }
+
         for (instr_index, instr) in func.drain().into_iter().enumerate() {
             let concrete = Driver::concrete_instr(
                 instr,
@@ -331,6 +1742,108 @@ impl Driver {
         Ok(())
     }
 
+    /// Searches for a chain of function references that leads from _init back to _init, returning
+    /// the names of every function along the shortest such chain.
+    ///
+    /// Recursion that doesn't pass back through _init is ordinary runtime recursion and is allowed.
+    fn find_init_cycle(
+        init_func: &Function,
+        object_data: &[ObjectData],
+        master_symbol_table: &NameTable<MasterSymbolEntry>,
+        master_function_name_table: &NameTable<NonZeroUsize>,
+        temporary_function_vec: &[Function],
+    ) -> Option<Vec<String>> {
+        // Functions are identified by whether they are global, their name hash, and their file
+        let start = (true, init_func.name_hash(), init_func.object_data_index());
+
+        let mut parents = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            let (is_global, hash, object_data_index) = node;
+
+            let func = if is_global {
+                temporary_function_vec
+                    .iter()
+                    .find(|func| func.name_hash() == hash)
+            } else {
+                object_data[object_data_index]
+                    .local_function_table
+                    .get_by_hash(hash)
+            };
+
+            let Some(func) = func else {
+                continue;
+            };
+
+            for op in func.instructions().flat_map(|instr| match instr {
+                TempInstr::ZeroOp(_) => vec![],
+                TempInstr::OneOp(_, op1) => vec![*op1],
+                TempInstr::TwoOp(_, op1, op2) => vec![*op1, *op2],
+            }) {
+                let Some((ref_is_global, ref_hash)) = Driver::func_hash_from_op(
+                    &op,
+                    master_symbol_table,
+                    &object_data[object_data_index].local_symbol_table,
+                ) else {
+                    continue;
+                };
+
+                let ref_object_data_index = if ref_is_global {
+                    match temporary_function_vec
+                        .iter()
+                        .find(|func| func.name_hash() == ref_hash)
+                    {
+                        Some(referenced_func) => referenced_func.object_data_index(),
+                        None => continue,
+                    }
+                } else {
+                    object_data_index
+                };
+
+                let next = (ref_is_global, ref_hash, ref_object_data_index);
+
+                if next == start {
+                    let mut path = vec![node];
+                    let mut current = node;
+
+                    while current != start {
+                        current = parents[&current];
+                        path.push(current);
+                    }
+
+                    path.reverse();
+                    path.push(start);
+
+                    return Some(
+                        path.into_iter()
+                            .map(|(is_global, hash, object_data_index)| {
+                                let name_table = if is_global {
+                                    master_function_name_table
+                                } else {
+                                    &object_data[object_data_index].local_function_name_table
+                                };
+
+                                name_table
+                                    .get_by_hash(hash)
+                                    .map(|entry| entry.name().to_owned())
+                                    .unwrap_or_default()
+                            })
+                            .collect(),
+                    );
+                }
+
+                if visited.insert(next) {
+                    parents.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
     fn func_hash_from_op(
         op: &TempOperand,
         master_symbol_table: &NameTable<MasterSymbolEntry>,
@@ -361,134 +1874,207 @@ impl Driver {
         }
     }
 
+    /// Marks the function an operand refers to as referenced. Returns the function if this is the
+    /// first reference to it, because then its own references still have to be followed.
     fn add_func_ref_from_op(
         op: &TempOperand,
         func_ref_vec: &mut Vec<u64>,
         parent_object_data_index: usize,
-        object_data: &mut Vec<ObjectData>,
+        object_data: &mut [ObjectData],
         master_symbol_table: &NameTable<MasterSymbolEntry>,
-        temporary_function_vec: &Vec<Function>,
-    ) {
-        if let Some((is_global, hash)) = Driver::func_hash_from_op(
+        temporary_function_vec: &[Function],
+    ) -> Option<(u64, bool, usize)> {
+        let (is_global, hash) = Driver::func_hash_from_op(
             op,
             master_symbol_table,
             &object_data
                 .get(parent_object_data_index)
                 .unwrap()
                 .local_symbol_table,
-        ) {
-            let referenced_func_opt = {
-                if is_global {
-                    if !func_ref_vec.contains(&hash) {
-                        func_ref_vec.push(hash);
+        )?;
 
-                        let referenced_func = temporary_function_vec
-                            .iter()
-                            .find(|func| func.name_hash() == hash)
-                            .unwrap();
+        if is_global {
+            if func_ref_vec.contains(&hash) {
+                return None;
+            }
 
-                        let referenced_func_name_hash = referenced_func.name_hash();
-                        let func_object_data_index = referenced_func.object_data_index();
+            func_ref_vec.push(hash);
 
-                        Some((referenced_func_name_hash, func_object_data_index))
-                    } else {
-                        None
-                    }
-                } else {
-                    let parent_object_data = object_data.get_mut(parent_object_data_index).unwrap();
+            // A function symbol without a function section has nothing to follow, and is reported
+            // when its offset can't be found
+            let referenced_func = temporary_function_vec
+                .iter()
+                .find(|func| func.name_hash() == hash)?;
 
-                    if !parent_object_data.local_function_ref_vec.contains(&hash) {
-                        parent_object_data.local_function_ref_vec.push(hash);
+            Some((
+                referenced_func.name_hash(),
+                true,
+                referenced_func.object_data_index(),
+            ))
+        } else {
+            let parent_object_data = object_data.get_mut(parent_object_data_index).unwrap();
 
-                        let referenced_func = object_data
-                            .get(parent_object_data_index)
-                            .unwrap()
-                            .local_function_table
-                            .get_by_hash(hash)
-                            .unwrap();
+            if parent_object_data.local_function_ref_vec.contains(&hash) {
+                return None;
+            }
 
-                        let referenced_func_name_hash = referenced_func.name_hash();
-                        let func_object_data_index = referenced_func.object_data_index();
+            parent_object_data.local_function_ref_vec.push(hash);
 
-                        Some((referenced_func_name_hash, func_object_data_index))
-                    } else {
-                        None
-                    }
-                }
-            };
+            let referenced_func = parent_object_data.local_function_table.get_by_hash(hash)?;
 
-            if let Some((referenced_name_hash, referenced_object_data_index)) = referenced_func_opt
-            {
-                // Recurse.
-                Driver::add_func_refs_optimize(
-                    referenced_name_hash,
-                    is_global,
-                    func_ref_vec,
-                    referenced_object_data_index,
-                    object_data,
-                    master_symbol_table,
-                    temporary_function_vec,
-                );
-            }
+            Some((
+                referenced_func.name_hash(),
+                false,
+                referenced_func.object_data_index(),
+            ))
         }
     }
 
+    /// Marks every function that is reachable from this one as referenced. This uses a list of
+    /// functions still to be visited instead of recursion, so that a long chain of calls can't
+    /// overflow the stack.
+    ///
+    /// Each function that is reached for the first time is added to reach_edges along with the
+    /// function it was reached from.
+    #[allow(clippy::too_many_arguments)]
     fn add_func_refs_optimize(
         func_name_hash: u64,
         func_is_global: bool,
         func_ref_vec: &mut Vec<u64>,
         object_data_index: usize,
-        object_data: &mut Vec<ObjectData>,
+        object_data: &mut [ObjectData],
         master_symbol_table: &NameTable<MasterSymbolEntry>,
-        temporary_function_vec: &Vec<Function>,
+        temporary_function_vec: &[Function],
+        reach_edges: &mut Vec<(FuncRef, FuncRef)>,
     ) {
+        let mut pending = vec![(func_name_hash, func_is_global, object_data_index)];
         let mut op_vec = Vec::with_capacity(16);
-        let parent_func = if func_is_global {
-            temporary_function_vec
-                .iter()
-                .find(|func| func.name_hash() == func_name_hash)
-                .unwrap()
-        } else {
-            object_data
-                .get(object_data_index)
-                .unwrap()
-                .local_function_table
-                .get_by_hash(func_name_hash)
-                .unwrap()
-        };
 
-        for instr in parent_func.instructions() {
-            match instr {
-                TempInstr::ZeroOp(_) => {}
-                TempInstr::OneOp(_, op1) => {
-                    op_vec.push(*op1);
+        while let Some((func_name_hash, func_is_global, object_data_index)) = pending.pop() {
+            let parent_func = if func_is_global {
+                temporary_function_vec
+                    .iter()
+                    .find(|func| func.name_hash() == func_name_hash)
+                    .unwrap()
+            } else {
+                object_data
+                    .get(object_data_index)
+                    .unwrap()
+                    .local_function_table
+                    .get_by_hash(func_name_hash)
+                    .unwrap()
+            };
+
+            op_vec.clear();
+
+            for instr in parent_func.instructions() {
+                match instr {
+                    TempInstr::ZeroOp(_) => {}
+                    TempInstr::OneOp(_, op1) => {
+                        op_vec.push(*op1);
+                    }
+                    TempInstr::TwoOp(_, op1, op2) => {
+                        op_vec.push(*op1);
+                        op_vec.push(*op2);
+                    }
                 }
-                TempInstr::TwoOp(_, op1, op2) => {
-                    op_vec.push(*op1);
-                    op_vec.push(*op2);
+            }
+
+            for op in op_vec.iter() {
+                if let Some(referenced_func) = Driver::add_func_ref_from_op(
+                    op,
+                    func_ref_vec,
+                    object_data_index,
+                    object_data,
+                    master_symbol_table,
+                    temporary_function_vec,
+                ) {
+                    reach_edges.push((
+                        (func_name_hash, func_is_global, object_data_index),
+                        referenced_func,
+                    ));
+                    pending.push(referenced_func);
                 }
             }
         }
+    }
 
-        for op in op_vec {
-            Driver::add_func_ref_from_op(
-                &op,
-                func_ref_vec,
-                object_data_index,
-                object_data,
-                master_symbol_table,
-                temporary_function_vec,
-            );
+    /// Renders why each function was kept as a tree under each root, with one line per function
+    /// saying which function reached it. A function is only listed under the first function that
+    /// reached it.
+    fn gc_graph_lines(
+        roots: &[FuncRef],
+        edges: &[(FuncRef, FuncRef)],
+        object_data: &[ObjectData],
+        master_function_name_table: &NameTable<NonZeroUsize>,
+    ) -> Vec<String> {
+        let name = |(func_name_hash, is_global, object_data_index): FuncRef| {
+            let name_table = if is_global {
+                master_function_name_table
+            } else {
+                &object_data[object_data_index].local_function_name_table
+            };
+
+            name_table
+                .get_by_hash(func_name_hash)
+                .map(|entry| entry.name().as_str())
+                .unwrap_or("<unknown>")
+        };
+
+        let describe = |func: FuncRef| {
+            format!(
+                "({}, {})",
+                if func.1 { "global" } else { "local" },
+                object_data[func.2].input_file_name
+            )
+        };
+
+        // Reversed so that popping them off of a stack gives them in the order they were found
+        let children = |func: FuncRef| {
+            edges
+                .iter()
+                .rev()
+                .filter(move |(from, _)| *from == func)
+                .map(|(_, to)| *to)
+        };
+
+        let mut lines = Vec::new();
+
+        for root in roots {
+            lines.push(format!("{} {}", name(*root), describe(*root)));
+
+            let mut pending: Vec<(FuncRef, FuncRef, usize)> =
+                children(*root).map(|child| (*root, child, 1)).collect();
+
+            while let Some((from, func, depth)) = pending.pop() {
+                lines.push(format!(
+                    "{:indent$}{} -> {} {}",
+                    "",
+                    name(from),
+                    name(func),
+                    describe(func),
+                    indent = depth * 2
+                ));
+
+                pending.extend(children(func).map(|child| (func, child, depth + 1)));
+            }
         }
+
+        lines
     }
 
+    /// Records where a function will be placed, rounding its offset up to a multiple of align.
+    /// Returns the number of instructions of padding needed before it, and the offset just after it.
     fn calc_func_offset(
         func: &Function,
         object_data: &mut ObjectData,
         func_hash_map: &mut HashMap<u64, usize>,
         current_offset: usize,
-    ) -> usize {
+        align: usize,
+    ) -> (usize, usize) {
         let size = func.instruction_count();
+        let padding = current_offset.next_multiple_of(align) - current_offset;
+        let current_offset = current_offset + padding;
 
         if func.is_global() {
             func_hash_map.insert(func.name_hash(), current_offset);
@@ -498,7 +2084,7 @@ impl Driver {
                 .insert(func.name_hash(), current_offset);
         }
 
-        current_offset + size
+        (padding, current_offset + size)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -521,7 +2107,12 @@ impl Driver {
             Some(func) => func.name(),
             None => master_function_name_table
                 .get_by_hash(func_name_hash)
-                .unwrap()
+                .ok_or_else(|| {
+                    LinkError::InternalError(format!(
+                        "Function with name hash {} has no name.",
+                        func_name_hash
+                    ))
+                })?
                 .name(),
         };
 
@@ -533,6 +2124,7 @@ impl Driver {
                     arg_section,
                     master_symbol_table,
                     master_data_table,
+                    master_function_name_table,
                     func_hash_map,
                     data_hash_map,
                     object_data,
@@ -548,6 +2140,7 @@ impl Driver {
                     arg_section,
                     master_symbol_table,
                     master_data_table,
+                    master_function_name_table,
                     func_hash_map,
                     data_hash_map,
                     object_data,
@@ -559,6 +2152,7 @@ impl Driver {
                     arg_section,
                     master_symbol_table,
                     master_data_table,
+                    master_function_name_table,
                     func_hash_map,
                     data_hash_map,
                     object_data,
@@ -577,6 +2171,7 @@ impl Driver {
         arg_section: &mut ArgumentSection,
         master_symbol_table: &NameTable<MasterSymbolEntry>,
         master_data_table: &DataTable,
+        master_function_name_table: &NameTable<NonZeroUsize>,
         func_hash_map: &HashMap<u64, usize>,
         data_hash_map: &mut HashMap<u64, ArgIndex>,
         object_data: &ObjectData,
@@ -588,7 +2183,9 @@ impl Driver {
                 Some(index) => Ok(*index),
                 None => {
                     // We do this nonsense so that only referenced data is included in the final binary
-                    let value = master_data_table.get_by_hash(hash).unwrap();
+                    let value = master_data_table.get_by_hash(hash).ok_or_else(|| {
+                        LinkError::InternalError(format!("No data value has hash {}.", hash))
+                    })?;
                     let index = arg_section.add(value.clone());
                     data_hash_map.insert(hash, index);
 
@@ -601,10 +2198,27 @@ impl Driver {
                     None => match master_symbol_table.get_by_hash(hash) {
                         Some(entry) => entry.value().internal(),
                         None => {
+                            let func_error_context = FuncErrorContext {
+                                file_context: FileErrorContext {
+                                    input_file_name: object_data.input_file_name.to_owned(),
+                                    source_file_name: object_data.source_file_name.to_owned(),
+                                },
+                                func_name: func_name.to_owned(),
+                            };
+
+                            // Fall back to the hash if the name can't be found anywhere
+                            let symbol_name = Driver::symbol_name_from_hash(
+                                hash,
+                                object_data,
+                                master_symbol_table,
+                                master_function_name_table,
+                            )
+                            .unwrap_or_else(|| format!("<unknown, hash {}>", hash));
+
                             return Err(LinkError::InvalidSymbolRefError(
-                                func_name.to_owned(),
+                                func_error_context,
                                 instr_index,
-                                hash,
+                                symbol_name,
                             ));
                         }
                     },
@@ -613,15 +2227,21 @@ impl Driver {
                 match sym.sym_type {
                     SymType::Func => {
                         let func_loc = if sym.sym_bind == SymBind::Global {
-                            func_hash_map.get(&hash).unwrap()
+                            func_hash_map.get(&hash)
                         } else {
-                            object_data.local_function_hash_map.get(&hash).unwrap()
-                        };
+                            object_data.local_function_hash_map.get(&hash)
+                        }
+                        .ok_or_else(|| {
+                            LinkError::InternalError(format!(
+                                "Function with name hash {} has no offset.",
+                                hash
+                            ))
+                        })?;
 
                         // Construct a new String that contains the destination label
-                        let value = KOSValue::String(format!("@{:0>4}", *func_loc));
+                        let value = KOSValue::String(validate::label(*func_loc));
 
-                        let mut hasher = DefaultHasher::new();
+                        let mut hasher = NameHasher::default();
                         value.hash(&mut hasher);
                         let data_hash = hasher.finish();
 
@@ -640,12 +2260,21 @@ impl Driver {
                         let index =
                             unsafe { NonZeroUsize::new_unchecked(usize::from(sym.value_idx) + 1) };
 
-                        let data_hash = master_data_table.hash_at(index).unwrap();
+                        let invalid_index = || {
+                            LinkError::InternalError(format!(
+                                "Symbol with name hash {} has invalid data index {}.",
+                                hash, index
+                            ))
+                        };
+
+                        let data_hash =
+                            master_data_table.hash_at(index).ok_or_else(invalid_index)?;
 
                         match data_hash_map.get(data_hash) {
                             Some(index) => Ok(*index),
                             None => {
-                                let value = master_data_table.get_at(index).unwrap();
+                                let value =
+                                    master_data_table.get_at(index).ok_or_else(invalid_index)?;
                                 let index = arg_section.add(value.clone());
                                 data_hash_map.insert(*data_hash, index);
 
@@ -653,20 +2282,327 @@ impl Driver {
                             }
                         }
                     }
-                    _ => unreachable!("Symbol type is not of NoType or Func"),
+                    _ => Err(LinkError::InternalError(format!(
+                        "Symbol with name hash {} is not of NoType or Func.",
+                        hash
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// The instructions of a function that can be inlined, without its return. That is one that ends
+    /// in the only return it has, which doesn't leave any scopes, and has at most the threshold of
+    /// instructions before it. Only leaf functions without any jumps are inlined, so that none of the
+    /// instructions refer to where they are, and every operand has to be a plain value.
+    fn inline_body(
+        func: &Function,
+        inline_threshold: usize,
+        master_data_table: &DataTable,
+    ) -> Option<Vec<TempInstr>> {
+        let (ret, body) = func.instructions().as_slice().split_last()?;
+
+        let TempInstr::OneOp(Opcode::Ret, TempOperand::DataHash(depth_hash)) = ret else {
+            return None;
+        };
+
+        let returns_from_function_scope = matches!(
+            master_data_table.get_by_hash(*depth_hash),
+            Some(
+                KOSValue::Byte(0)
+                    | KOSValue::Int16(0)
+                    | KOSValue::Int32(0)
+                    | KOSValue::ScalarInt(0)
+            )
+        );
+
+        if !returns_from_function_scope || body.len() > inline_threshold {
+            return None;
+        }
+
+        let inlinable = |instr: &TempInstr| {
+            let (opcode, operands) = match instr {
+                TempInstr::ZeroOp(opcode) => (*opcode, Vec::new()),
+                TempInstr::OneOp(opcode, op) => (*opcode, vec![*op]),
+                TempInstr::TwoOp(opcode, op1, op2) => (*opcode, vec![*op1, *op2]),
+            };
+
+            !matches!(
+                opcode,
+                Opcode::Call
+                    | Opcode::Ret
+                    | Opcode::Jmp
+                    | Opcode::Bfa
+                    | Opcode::Btr
+                    | Opcode::Eop
+                    | Opcode::Eof
+                    | Opcode::Lbrt
+                    | Opcode::Bscp
+                    | Opcode::Escp
+                    | Opcode::Phdl
+                    | Opcode::Pdrl
+            ) && operands
+                .iter()
+                .all(|op| matches!(op, TempOperand::DataHash(_)))
+        };
+
+        body.iter().all(inlinable).then(|| body.to_vec())
+    }
+
+    /// Replaces every call in a function to one of the inlinable functions with that function's
+    /// body. Returns the number of calls that were replaced.
+    fn inline_calls(
+        func: &mut Function,
+        inline_bodies: &HashMap<u64, Vec<TempInstr>>,
+        object_data: &ObjectData,
+        master_symbol_table: &NameTable<MasterSymbolEntry>,
+    ) -> usize {
+        if inline_bodies.is_empty() {
+            return 0;
+        }
+
+        let mut inlined_count = 0;
+        let instructions = func.drain();
+
+        for instr in instructions {
+            let callee = match instr {
+                TempInstr::TwoOp(Opcode::Call, TempOperand::SymNameHash(hash), _) => {
+                    let callee_symbol = match object_data.local_symbol_table.get_by_hash(hash) {
+                        Some(local_sym) => Some(*local_sym.internal()),
+                        None => master_symbol_table
+                            .get_by_hash(hash)
+                            .map(|entry| *entry.value().internal()),
+                    };
+
+                    callee_symbol
+                        .filter(|symbol| symbol.sym_type == SymType::Func)
+                        .and_then(|_| inline_bodies.get(&hash))
+                }
+                _ => None,
+            };
+
+            match callee {
+                Some(body) => {
+                    for body_instr in body {
+                        func.add(*body_instr);
+                    }
+
+                    inlined_count += 1;
+                }
+                None => func.add(instr),
+            }
+        }
+
+        inlined_count
+    }
+
+    /// Checks if two functions would compile to the same instructions, comparing what each operand
+    /// refers to rather than where it is stored in its object file.
+    fn functions_identical(
+        func: &Function,
+        local_symbol_table: &SymbolTable,
+        other_func: &Function,
+        other_local_symbol_table: &SymbolTable,
+    ) -> bool {
+        let operands_identical = |op: &TempOperand, other_op: &TempOperand| match (op, other_op) {
+            // Data hashes are computed from the value itself, so they are the same in every file
+            (TempOperand::DataHash(hash), TempOperand::DataHash(other_hash)) => hash == other_hash,
+            // Local symbols can mean something different in each file, so only globals are compared
+            (TempOperand::SymNameHash(hash), TempOperand::SymNameHash(other_hash)) => {
+                hash == other_hash
+                    && local_symbol_table.get_by_hash(*hash).is_none()
+                    && other_local_symbol_table.get_by_hash(*other_hash).is_none()
+            }
+            _ => false,
+        };
+
+        func.instruction_count() == other_func.instruction_count()
+            && func
+                .instructions()
+                .zip(other_func.instructions())
+                .all(|pair| match pair {
+                    (TempInstr::ZeroOp(opcode), TempInstr::ZeroOp(other_opcode)) => {
+                        opcode == other_opcode
+                    }
+                    (TempInstr::OneOp(opcode, op1), TempInstr::OneOp(other_opcode, other_op1)) => {
+                        opcode == other_opcode && operands_identical(op1, other_op1)
+                    }
+                    (
+                        TempInstr::TwoOp(opcode, op1, op2),
+                        TempInstr::TwoOp(other_opcode, other_op1, other_op2),
+                    ) => {
+                        opcode == other_opcode
+                            && operands_identical(op1, other_op1)
+                            && operands_identical(op2, other_op2)
+                    }
+                    _ => false,
+                })
+    }
+
+    /// Attempts to recover the human-readable name of a symbol from its name hash by searching
+    /// every name table that could have recorded it
+    fn symbol_name_from_hash(
+        hash: u64,
+        object_data: &ObjectData,
+        master_symbol_table: &NameTable<MasterSymbolEntry>,
+        master_function_name_table: &NameTable<NonZeroUsize>,
+    ) -> Option<String> {
+        object_data
+            .symbol_name_table
+            .get_by_hash(hash)
+            .map(|entry| entry.name())
+            .or_else(|| {
+                object_data
+                    .local_function_name_table
+                    .get_by_hash(hash)
+                    .map(|entry| entry.name())
+            })
+            .or_else(|| {
+                master_symbol_table
+                    .get_by_hash(hash)
+                    .map(|entry| entry.name())
+            })
+            .or_else(|| {
+                master_function_name_table
+                    .get_by_hash(hash)
+                    .map(|entry| entry.name())
+            })
+            .cloned()
+    }
+
+    /// Describes where a symbol was defined, using its context to find the function if possible
+    fn definition_context(
+        ctx: ContextHash,
+        source_file_name: &str,
+        local_function_name_table: &NameTable<NonZeroUsize>,
+        master_function_name_table: &NameTable<NonZeroUsize>,
+    ) -> DefinitionContext {
+        let func_name = match ctx {
+            ContextHash::FuncNameHash(func_name_hash) => local_function_name_table
+                .get_by_hash(func_name_hash)
+                .or_else(|| master_function_name_table.get_by_hash(func_name_hash))
+                .map(|entry| entry.name().to_owned()),
+            ContextHash::FileNameHash(_) => None,
+        };
+
+        DefinitionContext {
+            source_file_name: source_file_name.to_owned(),
+            func_name,
+        }
+    }
+
+    /// Creates the master symbol table entry for a global definition, copying its data into the
+    /// master data table if it is a variable
+    fn master_symbol_entry(
+        symbol: &mut SymbolEntry,
+        data_table: &DataTable,
+        master_data_table: &mut DataTable,
+        object_data_index: usize,
+        hidden_symbol_hashes: &HashSet<u64>,
+    ) -> MasterSymbolEntry {
+        let new_data_idx = if symbol.internal().sym_type == SymType::NoType {
+            let data_index = unsafe {
+                NonZeroUsize::new_unchecked(usize::from(symbol.internal().value_idx) + 1)
+            };
+            let data = data_table.get_at(data_index).unwrap();
+
+            let (_, non_zero_idx) = master_data_table.add(data.clone());
+
+            DataIdx::from(non_zero_idx.get() - 1)
+        } else {
+            // If this is a function, set the data index to the placeholder, it won't be needed
+            DataIdx::PLACEHOLDER
+        };
+
+        symbol.internal_mut().value_idx = new_data_idx;
+
+        let mut new_symbol_entry = MasterSymbolEntry::new(*symbol.internal(), symbol.context());
+        new_symbol_entry.set_object_data_index(object_data_index);
+        new_symbol_entry.set_hidden(hidden_symbol_hashes.contains(&symbol.name_hash()));
+
+        new_symbol_entry
+    }
+
+    /// Points this file's references to wrapped symbols at their wrappers, and its references
+    /// to __real_ symbols at the originals. Definitions are left alone.
+    fn wrap_references(object_data: &mut ObjectData, wrapped_names: &HashMap<u64, (u64, String)>) {
+        if wrapped_names.is_empty() {
+            return;
+        }
+
+        let mut redirects = HashMap::new();
+
+        for symbol in object_data.symbol_table.symbols_mut() {
+            if symbol.internal().sym_bind != SymBind::Extern {
+                continue;
+            }
+
+            let Some((target_hash, target_name)) = wrapped_names.get(&symbol.name_hash()) else {
+                continue;
+            };
+
+            // The symbol has to be found under its new name when it is resolved
+            if let Some(name_entry) = object_data
+                .symbol_name_table
+                .get_by_hash(symbol.name_hash())
+            {
+                let entry = NameTableEntry::from(target_name.to_owned(), *name_entry.value());
+                object_data
+                    .symbol_name_table
+                    .raw_insert(*target_hash, entry);
+            }
+
+            redirects.insert(symbol.name_hash(), *target_hash);
+            symbol.set_name_hash(*target_hash);
+        }
+
+        if redirects.is_empty() {
+            return;
+        }
+
+        let redirect = |operand: &mut TempOperand| {
+            if let TempOperand::SymNameHash(hash) = operand {
+                if let Some(target_hash) = redirects.get(hash) {
+                    *hash = *target_hash;
+                }
+            }
+        };
+
+        for func in object_data
+            .function_table
+            .functions_mut()
+            .chain(object_data.local_function_table.functions_mut())
+        {
+            for instr in func.instructions_mut() {
+                match instr {
+                    TempInstr::ZeroOp(_) => {}
+                    TempInstr::OneOp(_, op1) => redirect(op1),
+                    TempInstr::TwoOp(_, op1, op2) => {
+                        redirect(op1);
+                        redirect(op2);
+                    }
                 }
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn resolve_symbols(
         master_symbol_table: &mut NameTable<MasterSymbolEntry>,
         master_data_table: &mut DataTable,
         master_function_name_table: &NameTable<NonZeroUsize>,
         file_name_hash: ContextHash,
         object_data: &mut ObjectData,
+        object_data_index: usize,
+        previous_object_data: &mut [ObjectData],
         comment: &mut Option<String>,
-        entry_point_hash: u64,
+        comment_func_hash: u64,
+        fold_identical: bool,
+        allow_multiple_definition: bool,
+        folded_count: &mut usize,
+        hidden_symbol_hashes: &HashSet<u64>,
+        warnings: &mut Vec<LinkWarning>,
+        trace: &mut SymbolTrace,
     ) -> LinkResult<()> {
         for mut symbol in object_data.symbol_table.drain() {
             let name_entry = object_data
@@ -681,40 +2617,58 @@ impl Driver {
                     // Set the context to be correct
                     symbol.set_context(file_name_hash);
 
-                    // If it is the entry point, try to set the comment
-                    if entry_point_hash == symbol.name_hash() {
+                    // If this file defines the entry point, or _init for a shared object, the
+                    // output gets its comment
+                    if comment_func_hash == symbol.name_hash()
+                        && symbol.internal().sym_bind != SymBind::Extern
+                    {
                         *comment = object_data.comment.clone();
                     }
+
+                    // Every function this file defines had its name added before its symbols
+                    // are resolved, so it can always be named in errors and summaries
+                    if object_data
+                        .function_table
+                        .get_by_hash(symbol.name_hash())
+                        .is_some()
+                        && master_function_name_table
+                            .get_by_hash(symbol.name_hash())
+                            .is_none()
+                    {
+                        return Err(LinkError::InternalError(String::from(
+                            "Function name missing from the master function name table.",
+                        )));
+                    }
                 }
 
+                trace.record(symbol.name_hash(), || {
+                    let kind = if symbol.internal().sym_bind == SymBind::Extern {
+                        "reference to"
+                    } else {
+                        "definition of"
+                    };
+
+                    format!(
+                        "{}: {} {}",
+                        object_data.input_file_name,
+                        kind,
+                        name_entry.name()
+                    )
+                });
+
                 match master_symbol_table.get_by_hash(symbol.name_hash()) {
                     Some(other_symbol) => {
                         // If the found symbol is external
                         if other_symbol.value().internal().sym_bind == SymBind::Extern {
                             // If this new symbol is _not_ external
                             if symbol.internal().sym_bind != SymBind::Extern {
-                                let new_data_idx = if symbol.internal().sym_type == SymType::NoType
-                                {
-                                    let data_index = unsafe {
-                                        NonZeroUsize::new_unchecked(
-                                            usize::from(symbol.internal().value_idx) + 1,
-                                        )
-                                    };
-                                    let data = object_data.data_table.get_at(data_index).unwrap();
-
-                                    let (_, non_zero_idx) = master_data_table.add(data.clone());
-
-                                    DataIdx::from(non_zero_idx.get() - 1)
-                                } else {
-                                    // If this is a function, set the data index to the placeholder, it won't be needed
-                                    DataIdx::PLACEHOLDER
-                                };
-
-                                symbol.internal_mut().value_idx = new_data_idx;
-                                let new_symbol = *symbol.internal();
-
-                                let new_symbol_entry =
-                                    MasterSymbolEntry::new(new_symbol, symbol.context());
+                                let new_symbol_entry = Driver::master_symbol_entry(
+                                    &mut symbol,
+                                    &object_data.data_table,
+                                    master_data_table,
+                                    object_data_index,
+                                    hidden_symbol_hashes,
+                                );
 
                                 // Replace it
                                 master_symbol_table
@@ -731,52 +2685,151 @@ impl Driver {
                         else {
                             // Check if we are not external
                             if symbol.internal().sym_bind != SymBind::Extern {
-                                // Duplicate symbol!
+                                // An identical copy of a function from another file can just be dropped
+                                if fold_identical && symbol.internal().sym_type == SymType::Func {
+                                    let original_func = previous_object_data
+                                        .get(other_symbol.value().object_data_index())
+                                        .and_then(|original| {
+                                            original
+                                                .function_table
+                                                .get_by_hash(symbol.name_hash())
+                                                .map(|func| (original, func))
+                                        });
+                                    let duplicate_func =
+                                        object_data.function_table.get_by_hash(symbol.name_hash());
+
+                                    if let (Some((original, original_func)), Some(duplicate_func)) =
+                                        (original_func, duplicate_func)
+                                    {
+                                        if Driver::functions_identical(
+                                            original_func,
+                                            &original.local_symbol_table,
+                                            duplicate_func,
+                                            &object_data.local_symbol_table,
+                                        ) {
+                                            object_data
+                                                .function_table
+                                                .remove_by_hash(symbol.name_hash());
+                                            *folded_count += 1;
+
+                                            trace.record(symbol.name_hash(), || {
+                                                format!(
+                                                    "{}: {} is identical to the definition in {}, which is used instead",
+                                                    object_data.input_file_name,
+                                                    name_entry.name(),
+                                                    original.input_file_name
+                                                )
+                                            });
+
+                                            continue;
+                                        }
+                                    }
+                                }
 
-                                let file_error_context = FileErrorContext {
-                                    input_file_name: object_data.input_file_name.to_owned(),
-                                    source_file_name: object_data.source_file_name.to_owned(),
-                                };
+                                // Duplicate symbol!
 
-                                let mut func_error_context = FuncErrorContext {
-                                    file_context: file_error_context.clone(),
-                                    func_name: String::new(),
-                                };
+                                let original_object_data_index =
+                                    other_symbol.value().object_data_index();
 
-                                let mut original_func_name = None;
+                                // Two clashing symbols can even come from the same file
+                                let (original_source_file_name, original_local_function_names) =
+                                    match previous_object_data.get(original_object_data_index) {
+                                        Some(original) => (
+                                            &original.source_file_name,
+                                            &original.local_function_name_table,
+                                        ),
+                                        None => (
+                                            &object_data.source_file_name,
+                                            &object_data.local_function_name_table,
+                                        ),
+                                    };
 
-                                if let ContextHash::FuncNameHash(func_name_hash) =
-                                    other_symbol.value().context()
+                                let original_context = Driver::definition_context(
+                                    other_symbol.value().context(),
+                                    original_source_file_name,
+                                    original_local_function_names,
+                                    master_function_name_table,
+                                );
+                                let duplicate_context = Driver::definition_context(
+                                    symbol.context(),
+                                    &object_data.source_file_name,
+                                    &object_data.local_function_name_table,
+                                    master_function_name_table,
+                                );
+
+                                let original_type = other_symbol.value().internal().sym_type;
+
+                                // The last definition wins, as long as it is the same kind of symbol
+                                if allow_multiple_definition
+                                    && original_type == symbol.internal().sym_type
                                 {
-                                    let original_function_name_entry = master_function_name_table
-                                        .get_by_hash(func_name_hash)
-                                        .unwrap();
-                                    let original_function_name =
-                                        original_function_name_entry.name();
+                                    // Otherwise both copies of the function would be emitted
+                                    if original_type == SymType::Func {
+                                        if let Some(original) =
+                                            previous_object_data.get_mut(original_object_data_index)
+                                        {
+                                            original
+                                                .function_table
+                                                .remove_by_hash(symbol.name_hash());
+                                        }
+                                    }
 
-                                    original_func_name = Some(original_function_name.to_owned());
+                                    let new_symbol_entry = Driver::master_symbol_entry(
+                                        &mut symbol,
+                                        &object_data.data_table,
+                                        master_data_table,
+                                        object_data_index,
+                                        hidden_symbol_hashes,
+                                    );
+
+                                    master_symbol_table
+                                        .replace_by_hash(symbol.name_hash(), new_symbol_entry)
+                                        .map_err(|_| {
+                                            LinkError::InternalError(String::from(
+                                                "Symbol name hash invalid.",
+                                            ))
+                                        })?;
+
+                                    trace.record(symbol.name_hash(), || {
+                                        // Two clashing symbols can even come from the same file
+                                        let original_file_name = previous_object_data
+                                            .get(original_object_data_index)
+                                            .map_or(&object_data.input_file_name, |original| {
+                                                &original.input_file_name
+                                            });
+
+                                        format!(
+                                            "{}: {} replaces the definition in {}",
+                                            object_data.input_file_name,
+                                            name_entry.name(),
+                                            original_file_name
+                                        )
+                                    });
+
+                                    warnings.push(LinkWarning::MultipleDefinitionWarning(
+                                        name_entry.name().to_owned(),
+                                        Box::new(original_context),
+                                        Box::new(duplicate_context),
+                                    ));
+
+                                    continue;
                                 }
 
-                                return Err(match original_func_name {
-                                    Some(name) => {
-                                        func_error_context.func_name = name;
+                                let file_error_context = FileErrorContext {
+                                    input_file_name: object_data.input_file_name.to_owned(),
+                                    source_file_name: object_data.source_file_name.to_owned(),
+                                };
 
-                                        LinkError::FuncContextError(
-                                            func_error_context,
-                                            ProcessingError::DuplicateSymbolError(
-                                                name_entry.name().to_owned(),
-                                                object_data.source_file_name.to_owned(),
-                                            ),
-                                        )
-                                    }
-                                    None => LinkError::FileContextError(
-                                        file_error_context,
-                                        ProcessingError::DuplicateSymbolError(
-                                            name_entry.name().to_owned(),
-                                            object_data.source_file_name.to_owned(),
-                                        ),
+                                return Err(LinkError::FileContextError(
+                                    file_error_context,
+                                    ProcessingError::DuplicateSymbolError(
+                                        name_entry.name().to_owned(),
+                                        original_type,
+                                        symbol.internal().sym_type,
+                                        Box::new(original_context),
+                                        Box::new(duplicate_context),
                                     ),
-                                });
+                                ));
                             }
                             // If we are external, then just continue
                         }
@@ -803,11 +2856,22 @@ impl Driver {
                             *symbol.internal()
                         };
 
-                        let new_symbol_entry = MasterSymbolEntry::new(new_symbol, symbol.context());
+                        let mut new_symbol_entry =
+                            MasterSymbolEntry::new(new_symbol, symbol.context());
+                        new_symbol_entry.set_object_data_index(object_data_index);
+                        new_symbol_entry
+                            .set_hidden(hidden_symbol_hashes.contains(&symbol.name_hash()));
                         let new_name_entry =
                             NameTableEntry::from(name_entry.name().to_owned(), new_symbol_entry);
 
-                        master_symbol_table.raw_insert(symbol.name_hash(), new_name_entry);
+                        let (_, inserted) =
+                            master_symbol_table.raw_insert(symbol.name_hash(), new_name_entry);
+
+                        if !inserted {
+                            return Err(LinkError::InternalError(String::from(
+                                "Symbol was already in the master symbol table.",
+                            )));
+                        }
                     }
                 }
             }
@@ -1,33 +1,167 @@
+use flate2::read::GzDecoder;
 use kerbalobjects::BufferIterator;
+use std::borrow::Cow;
 use std::collections::hash_map::Entry;
 use std::path::PathBuf;
-use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    ffi::OsString,
-    hash::Hasher,
-    io::Read,
-    num::NonZeroUsize,
-};
+use std::{collections::HashMap, ffi::OsString, hash::Hasher, io::Read, num::NonZeroUsize};
 
-use kerbalobjects::ko::sections::{DataIdx, InstrIdx, StringIdx, SymbolIdx};
+use kerbalobjects::ko::sections::{DataIdx, InstrIdx, SymbolIdx};
 use kerbalobjects::ko::symbols::OperandIndex;
 use kerbalobjects::ko::{
-    sections::ReldSection,
-    symbols::{SymBind, SymType},
-    KOFile, SectionIdx,
+    sections::{ReldSection, SectionHeader, StringTable},
+    symbols::{KOSymbol, SymBind, SymType},
+    KOFile, KOHeader, SectionIdx,
 };
 
 use crate::tables::{
-    ContextHash, DataTable, Function, FunctionTable, NameTable, NameTableEntry, ObjectData,
-    SymbolEntry, SymbolTable, TempInstr, TempOperand,
+    local_name_hash, ContextHash, DataTable, Function, FunctionTable, NameHasher, NameTable,
+    NameTableEntry, ObjectData, SymbolEntry, SymbolTable, TempInstr, TempOperand,
 };
 
+use super::archive::Archive;
 use super::errors::{FileErrorContext, FuncErrorContext, LinkError, LinkResult, ProcessingError};
 
+/// Maps each function section to the symbols referenced by each of its instructions' operands
+type ReldMap = HashMap<SectionIdx, HashMap<InstrIdx, (Option<SymbolIdx>, Option<SymbolIdx>)>>;
+
+/// The first two bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 pub struct Reader {}
 
 impl Reader {
+    /// Reads and parses the KO file at the given path, returning its file name along with it
     pub fn read_file(path: impl Into<PathBuf>) -> LinkResult<(String, KOFile)> {
+        let (file_name, buffer) = Reader::read_buffer(path)?;
+        let kofile = Reader::read_bytes(&file_name, &buffer)?;
+
+        Ok((file_name, kofile))
+    }
+
+    /// Reads a KO file from anything that can be read from, such as a file or a virtual file
+    /// system, and parses it. The file name is only used for error messages.
+    pub fn read_from(file_name: &str, reader: impl Read) -> LinkResult<KOFile> {
+        let buffer = Reader::read_all(file_name, reader)?;
+
+        Reader::read_bytes(file_name, &buffer)
+    }
+
+    fn read_all(file_name: &str, mut reader: impl Read) -> LinkResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(2048);
+
+        reader
+            .read_to_end(&mut buffer)
+            .map_err(|e| LinkError::IOError(OsString::from(file_name), e.kind()))?;
+
+        Ok(buffer)
+    }
+
+    /// Parses a KO file that is already in memory. The file name is only used for error messages.
+    ///
+    /// KO files that were compressed with gzip are decompressed first.
+    pub fn read_bytes(file_name: &str, bytes: &[u8]) -> LinkResult<KOFile> {
+        // Otherwise this is only a generic error reading the header at byte 0
+        if bytes.is_empty() {
+            return Err(LinkError::EmptyInputFileError(file_name.to_owned()));
+        }
+
+        let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = Vec::with_capacity(bytes.len() * 4);
+
+            GzDecoder::new(bytes)
+                .read_to_end(&mut decompressed)
+                .map_err(|e| LinkError::IOError(OsString::from(file_name), e.kind()))?;
+
+            Cow::Owned(decompressed)
+        } else {
+            Cow::Borrowed(bytes)
+        };
+
+        let mut buffer_iter = BufferIterator::new(&bytes);
+
+        KOFile::parse(&mut buffer_iter).map_err(|error| {
+            let offset = buffer_iter.current_index();
+
+            LinkError::FileReadError(
+                OsString::from(file_name),
+                error,
+                offset,
+                Reader::section_at(&bytes, offset),
+            )
+        })
+    }
+
+    /// Finds which part of a KO file a byte offset is in, so that an error parsing it can say
+    /// where it happened. Returns None if the headers are too broken to tell.
+    ///
+    /// The .shstrtab comes right after the section header table, and every other section follows
+    /// in the order of its header.
+    fn section_at(bytes: &[u8], offset: usize) -> Option<String> {
+        let mut source = BufferIterator::new(bytes);
+
+        let header = match KOHeader::parse(&mut source) {
+            Ok(header) if offset >= source.current_index() => header,
+            _ => return Some(String::from("the file header")),
+        };
+
+        let mut section_headers = Vec::with_capacity(header.num_headers as usize);
+
+        for _ in 0..header.num_headers {
+            match SectionHeader::parse(&mut source) {
+                Ok(section_header) if offset >= source.current_index() => {
+                    section_headers.push(section_header)
+                }
+                _ => return Some(String::from("the section header table")),
+            }
+        }
+
+        let shstrtab_header = section_headers.get(usize::from(header.shstrtab_idx))?;
+        let mut section_end = source.current_index() + shstrtab_header.size as usize;
+
+        if offset < section_end {
+            return Some(String::from(".shstrtab"));
+        }
+
+        let shstrtab =
+            StringTable::parse(&mut source, shstrtab_header.size, header.shstrtab_idx).ok()?;
+
+        for (section_idx, section_header) in section_headers.iter().enumerate().skip(1) {
+            if section_idx == usize::from(header.shstrtab_idx) {
+                continue;
+            }
+
+            section_end += section_header.size as usize;
+
+            if offset < section_end {
+                return shstrtab.get(section_header.name_idx).cloned();
+            }
+        }
+
+        None
+    }
+
+    /// Reads a KerbalObject archive, returning the archive's file name and every member KO file.
+    ///
+    /// Members are named after the archive they came from, like `libmath.kar(vector.ko)`
+    pub fn read_archive(path: impl Into<PathBuf>) -> LinkResult<(String, Vec<(String, KOFile)>)> {
+        let (file_name, buffer) = Reader::read_buffer(path)?;
+
+        let archive = Archive::parse(&buffer)
+            .map_err(|error| LinkError::ArchiveReadError(OsString::from(&file_name), error))?;
+
+        let mut members = Vec::new();
+
+        for member in archive.members() {
+            let member_name = format!("{}({})", file_name, member.name());
+            let kofile = Reader::read_bytes(&member_name, member.data())?;
+
+            members.push((member_name, kofile));
+        }
+
+        Ok((file_name, members))
+    }
+
+    fn read_buffer(path: impl Into<PathBuf>) -> LinkResult<(String, Vec<u8>)> {
         let path = path.into();
 
         let file_name_os = path
@@ -38,28 +172,37 @@ impl Reader {
             .into_string()
             .map_err(|_| LinkError::StringConversionError)?;
 
-        let mut buffer = Vec::with_capacity(2048);
-        let mut file = std::fs::File::open(&path)
+        let file = std::fs::File::open(&path)
             .map_err(|e| LinkError::IOError(OsString::from(file_name_os), e.kind()))?;
-        file.read_to_end(&mut buffer).unwrap();
-        let mut buffer_iter = BufferIterator::new(&buffer);
-
-        Ok((
-            file_name,
-            KOFile::parse(&mut buffer_iter)
-                .map_err(|error| LinkError::FileReadError(OsString::from(file_name_os), error))?,
-        ))
+        let buffer = Reader::read_all(&file_name, file)?;
+
+        Ok((file_name, buffer))
     }
 
-    pub fn process_file(file_name: String, kofile: KOFile) -> LinkResult<ObjectData> {
-        let mut hasher = DefaultHasher::new();
+    /// Processes a KO file into the tables the driver links from.
+    ///
+    /// This is also the way to inspect what a single KO file provides without linking it. Its global
+    /// functions and symbols are in function_table and symbol_table, with their names in
+    /// function_name_table and symbol_name_table, and its local ones are kept separately.
+    ///
+    /// If resolve_direct_symrefs is set, an operand with no relocation entry that isn't a valid data
+    /// index is treated as a symbol table index, which is how some assemblers encode references.
+    ///
+    /// If require_reld is set, a file with functions and no .reld section is rejected if it
+    /// declares any symbol that its functions could only reference through a relocation.
+    pub fn process_file(
+        file_name: String,
+        kofile: KOFile,
+        resolve_direct_symrefs: bool,
+        require_reld: bool,
+    ) -> LinkResult<ObjectData> {
+        let mut hasher = NameHasher::default();
 
         hasher.write(file_name.as_bytes());
-        let file_name_hash = ContextHash::FileNameHash(hasher.finish());
+        let file_hash = hasher.finish();
+        let file_name_hash = ContextHash::FileNameHash(file_hash);
 
-        let comment = kofile
-            .str_tab_by_name(".comment")
-            .and_then(|section| section.get(StringIdx::from(1u32)).cloned());
+        let comment = Reader::comment(&kofile);
 
         let symtab = kofile.sym_tab_by_name(".symtab").ok_or_else(|| {
             LinkError::MissingSectionError(file_name.to_owned(), String::from(".symtab"))
@@ -70,10 +213,10 @@ impl Reader {
         let data_section = kofile.data_section_by_name(".data").ok_or_else(|| {
             LinkError::MissingSectionError(file_name.to_owned(), String::from(".data"))
         })?;
+        // A file with no relocations has no need for a .reld section
         let reld_section_opt = kofile.reld_section_by_name(".reld");
 
-        let mut reld_map =
-            HashMap::<SectionIdx, HashMap<InstrIdx, (Option<SymbolIdx>, Option<SymbolIdx>)>>::new();
+        let mut reld_map = ReldMap::new();
 
         let mut symbol_table = SymbolTable::new();
         let mut function_table = FunctionTable::new();
@@ -89,24 +232,19 @@ impl Reader {
 
         if let Some(reld_section) = reld_section_opt {
             Reader::process_relocations(reld_section, &mut reld_map);
+        } else if require_reld
+            && !resolve_direct_symrefs
+            && kofile.func_sections().next().is_some()
+            && Reader::has_relocatable_symbols(symtab)
+        {
+            // Without relocations, every operand would be read as a data index
+            return Err(LinkError::MissingSectionError(
+                file_name.to_owned(),
+                String::from(".reld"),
+            ));
         }
 
-        let mut file_symbol_opt = None;
-
-        // Find the file symbol
-        for symbol in symtab.symbols() {
-            if symbol.sym_type == SymType::File {
-                file_symbol_opt = Some(symbol);
-                break;
-            }
-        }
-
-        let file_symbol = file_symbol_opt
-            .ok_or_else(|| LinkError::MissingFileSymbolError(file_name.to_owned()))?;
-        let source_file_name = symstrtab
-            .get(file_symbol.name_idx)
-            .ok_or_else(|| LinkError::MissingFileSymbolNameError(file_name.to_owned()))?
-            .to_owned();
+        let source_file_name = Reader::source_file_name(&file_name, &kofile)?;
 
         let file_error_context = FileErrorContext {
             input_file_name: file_name.to_owned(),
@@ -147,14 +285,20 @@ impl Reader {
             let func_name_idx = symstrtab.position(name).ok_or_else(|| {
                 LinkError::FuncContextError(
                     func_error_context.to_owned(),
-                    ProcessingError::FuncMissingSymbolError,
+                    ProcessingError::FuncMissingSymbolError(
+                        name.to_owned(),
+                        u16::from(func_section.section_index()),
+                    ),
                 )
             })?;
 
             let func_symbol = symtab.find_by_name(func_name_idx).ok_or_else(|| {
                 LinkError::FuncContextError(
                     func_error_context.to_owned(),
-                    ProcessingError::FuncMissingSymbolError,
+                    ProcessingError::FuncMissingSymbolError(
+                        name.to_owned(),
+                        u16::from(func_section.section_index()),
+                    ),
                 )
             })?;
 
@@ -168,29 +312,91 @@ impl Reader {
             let func_name_table_entry =
                 NameTableEntry::from(name.to_owned(), unsafe { NonZeroUsize::new_unchecked(1) }); // 1 is a placeholder because there is no file name table to reference
 
-            hasher = DefaultHasher::new();
-            hasher.write(name.as_bytes());
+            let is_global = func_symbol.sym_bind == SymBind::Global;
 
-            let hash_value = hasher.finish();
+            let hash_value = if is_global {
+                hasher = NameHasher::default();
+                hasher.write(name.as_bytes());
+                hasher.finish()
+            } else {
+                local_name_hash(name, file_hash)
+            };
 
             let func_name_hash = ContextHash::FuncNameHash(hash_value);
 
-            let mut function_entry =
-                Function::new(hash_value, func_symbol.sym_bind == SymBind::Global);
+            let mut function_entry = Function::new(hash_value, is_global);
+            function_entry.set_section_index(u16::from(func_section.section_index()));
 
             let func_reld = reld_map.get(&func_section.section_index());
 
+            // Otherwise a relocation past the end of the function would never be looked up
+            let instr_count = func_section.instructions().count();
+
+            if let Some(instr_index) = func_reld.and_then(|reld| {
+                reld.keys()
+                    .map(|instr_index| usize::from(*instr_index))
+                    .filter(|instr_index| *instr_index >= instr_count)
+                    .min()
+            }) {
+                return Err(LinkError::FuncContextError(
+                    func_error_context,
+                    ProcessingError::InvalidRelocationInstrError(instr_index, instr_count),
+                ));
+            }
+
             for (i, instr) in func_section
                 .instructions()
                 .enumerate()
                 .map(|(i, v)| (InstrIdx::from(i), v))
             {
+                // Files built in memory never went through the parser, which would have read the
+                // operands each opcode takes
+                let (opcode, operand_count) = match instr {
+                    kerbalobjects::ko::instructions::Instr::ZeroOp(opcode) => (*opcode, 0),
+                    kerbalobjects::ko::instructions::Instr::OneOp(opcode, _) => (*opcode, 1),
+                    kerbalobjects::ko::instructions::Instr::TwoOp(opcode, _, _) => (*opcode, 2),
+                };
+
+                if operand_count != opcode.num_operands() {
+                    return Err(LinkError::FuncContextError(
+                        func_error_context,
+                        ProcessingError::OpcodeArityMismatchError(
+                            usize::from(i),
+                            opcode,
+                            operand_count,
+                            opcode.num_operands(),
+                        ),
+                    ));
+                }
+
                 let temp_instr = match instr {
                     kerbalobjects::ko::instructions::Instr::ZeroOp(opcode) => {
+                        // A corrupt .reld section could relocate an operand that doesn't exist
+                        if let Some(data) = func_reld.and_then(|reld| reld.get(&i)) {
+                            let operand_index = if data.0.is_some() { 1 } else { 2 };
+
+                            return Err(LinkError::FuncContextError(
+                                func_error_context,
+                                ProcessingError::InvalidRelocationOperandError(
+                                    usize::from(i),
+                                    operand_index,
+                                ),
+                            ));
+                        }
+
                         TempInstr::ZeroOp(*opcode)
                     }
                     kerbalobjects::ko::instructions::Instr::OneOp(opcode, op1) => {
                         match func_reld.and_then(|reld| reld.get(&i)) {
+                            Some(data) if data.1.is_some() => {
+                                return Err(LinkError::FuncContextError(
+                                    func_error_context,
+                                    ProcessingError::InvalidRelocationOperandError(
+                                        usize::from(i),
+                                        2,
+                                    ),
+                                ));
+                            }
                             Some(data) => TempInstr::OneOp(
                                 *opcode,
                                 Reader::tempop_from(
@@ -202,19 +408,33 @@ impl Reader {
                                     &mut symbol_table,
                                     &mut symbol_name_table,
                                     &mut local_symbol_table,
+                                    file_hash,
                                     func_name_hash,
                                     i,
+                                    OperandIndex::One,
                                     data.0,
                                     *op1,
+                                    resolve_direct_symrefs,
                                 )?,
                             ),
                             None => TempInstr::OneOp(
                                 *opcode,
-                                Reader::data_tempop_from(
+                                Reader::tempop_from(
+                                    symtab,
+                                    symstrtab,
                                     &func_error_context,
                                     &data_index_map,
+                                    &mut referenced_symbol_map,
+                                    &mut symbol_table,
+                                    &mut symbol_name_table,
+                                    &mut local_symbol_table,
+                                    file_hash,
+                                    func_name_hash,
                                     i,
+                                    OperandIndex::One,
+                                    None,
                                     *op1,
+                                    resolve_direct_symrefs,
                                 )?,
                             ),
                         }
@@ -232,10 +452,13 @@ impl Reader {
                                     &mut symbol_table,
                                     &mut symbol_name_table,
                                     &mut local_symbol_table,
+                                    file_hash,
                                     func_name_hash,
                                     i,
+                                    OperandIndex::One,
                                     data.0,
                                     *op1,
+                                    resolve_direct_symrefs,
                                 )?,
                                 Reader::tempop_from(
                                     symtab,
@@ -246,25 +469,50 @@ impl Reader {
                                     &mut symbol_table,
                                     &mut symbol_name_table,
                                     &mut local_symbol_table,
+                                    file_hash,
                                     func_name_hash,
                                     i,
+                                    OperandIndex::Two,
                                     data.1,
                                     *op2,
+                                    resolve_direct_symrefs,
                                 )?,
                             ),
                             None => TempInstr::TwoOp(
                                 *opcode,
-                                Reader::data_tempop_from(
+                                Reader::tempop_from(
+                                    symtab,
+                                    symstrtab,
                                     &func_error_context,
                                     &data_index_map,
+                                    &mut referenced_symbol_map,
+                                    &mut symbol_table,
+                                    &mut symbol_name_table,
+                                    &mut local_symbol_table,
+                                    file_hash,
+                                    func_name_hash,
                                     i,
+                                    OperandIndex::One,
+                                    None,
                                     *op1,
+                                    resolve_direct_symrefs,
                                 )?,
-                                Reader::data_tempop_from(
+                                Reader::tempop_from(
+                                    symtab,
+                                    symstrtab,
                                     &func_error_context,
                                     &data_index_map,
+                                    &mut referenced_symbol_map,
+                                    &mut symbol_table,
+                                    &mut symbol_name_table,
+                                    &mut local_symbol_table,
+                                    file_hash,
+                                    func_name_hash,
                                     i,
+                                    OperandIndex::Two,
+                                    None,
                                     *op2,
+                                    resolve_direct_symrefs,
                                 )?,
                             ),
                         }
@@ -274,21 +522,41 @@ impl Reader {
                 function_entry.add(temp_instr);
             }
 
-            if func_symbol.sym_bind == SymBind::Global {
+            if is_global {
                 function_name_table.insert(func_name_table_entry);
                 function_table.add(function_entry);
             } else {
-                local_function_name_table.insert(func_name_table_entry);
+                local_function_name_table.raw_insert(hash_value, func_name_table_entry);
                 local_function_table.add(function_entry);
             }
         }
 
+        let mut unreferenced_externs = Vec::new();
+
         // Add all non-referenced global symbols
         for (i, symbol) in symtab
             .symbols()
             .enumerate()
             .map(|(i, v)| (SymbolIdx::from(i), v))
         {
+            // External symbols that nothing uses don't need to be resolved, but are kept by name in
+            // case every external symbol has to be defined
+            if !referenced_symbol_map.contains_key(&i) && symbol.sym_bind == SymBind::Extern {
+                let name = symstrtab.get(symbol.name_idx).ok_or_else(|| {
+                    LinkError::FileContextError(
+                        file_error_context.clone(),
+                        ProcessingError::MissingSymbolNameError(
+                            usize::from(i),
+                            usize::from(symbol.name_idx),
+                        ),
+                    )
+                })?;
+
+                unreferenced_externs.push(name.to_owned());
+
+                continue;
+            }
+
             if !referenced_symbol_map.contains_key(&i)
                 && symbol.sym_bind == SymBind::Global
                 && symbol.sym_type != SymType::File
@@ -302,7 +570,7 @@ impl Reader {
                         ),
                     )
                 })?;
-                hasher = DefaultHasher::new();
+                hasher = NameHasher::default();
                 hasher.write(name.as_bytes());
                 let name_hash = hasher.finish();
 
@@ -341,14 +609,73 @@ impl Reader {
             function_table,
             symbol_table,
             data_table,
+            file_name_hash: file_hash,
             local_function_table,
             local_symbol_table,
             local_function_hash_map,
             local_function_name_table,
             local_function_ref_vec,
+            unreferenced_externs,
         })
     }
 
+    /// The name of the source file a KO file was produced from, which is the name of its file symbol.
+    /// A file with more than one file symbol was most likely concatenated or assembled wrongly, so it
+    /// is an error instead of a guess at which one is right.
+    pub fn source_file_name(file_name: &str, kofile: &KOFile) -> LinkResult<String> {
+        let symtab = kofile.sym_tab_by_name(".symtab").ok_or_else(|| {
+            LinkError::MissingSectionError(file_name.to_owned(), String::from(".symtab"))
+        })?;
+        let symstrtab = kofile.str_tab_by_name(".symstrtab").ok_or_else(|| {
+            LinkError::MissingSectionError(file_name.to_owned(), String::from(".symstrtab"))
+        })?;
+
+        let mut file_symbols = symtab
+            .symbols()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.sym_type == SymType::File);
+
+        let file_symbol = file_symbols
+            .next()
+            .ok_or_else(|| LinkError::MissingFileSymbolError(file_name.to_owned()))?;
+
+        let file_symbol_name = |(symbol_index, symbol): (usize, &KOSymbol)| {
+            symstrtab.get(symbol.name_idx).cloned().ok_or_else(|| {
+                LinkError::MissingFileSymbolNameError(
+                    file_name.to_owned(),
+                    symbol_index,
+                    usize::from(symbol.name_idx),
+                )
+            })
+        };
+
+        let source_file_name = file_symbol_name(file_symbol)?;
+
+        if let Some(other_file_symbol) = file_symbols.next() {
+            return Err(LinkError::DuplicateFileSymbolError(
+                file_name.to_owned(),
+                source_file_name,
+                file_symbol_name(other_file_symbol)?,
+            ));
+        }
+
+        Ok(source_file_name)
+    }
+
+    /// The comment of a KO file, if it has one. Each tool that produced the file may have left its
+    /// own comment, so every one in the .comment section is kept, one per line.
+    pub fn comment(kofile: &KOFile) -> Option<String> {
+        let section = kofile.str_tab_by_name(".comment")?;
+
+        let comments: Vec<&str> = section
+            .strings()
+            .map(|comment| comment.as_str())
+            .filter(|comment| !comment.is_empty())
+            .collect();
+
+        (!comments.is_empty()).then(|| comments.join("\n"))
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn tempop_from(
         symtab: &kerbalobjects::ko::sections::SymbolTable,
@@ -359,10 +686,13 @@ impl Reader {
         symbol_table: &mut SymbolTable,
         symbol_name_table: &mut NameTable<NonZeroUsize>,
         local_symbol_table: &mut SymbolTable,
+        file_hash: u64,
         func_name_hash: ContextHash,
         instr_index: InstrIdx,
+        operand_index: OperandIndex,
         reld_data: Option<SymbolIdx>,
         operand: DataIdx,
+        resolve_direct_symrefs: bool,
     ) -> LinkResult<TempOperand> {
         Ok(match reld_data {
             Some(sym_idx) => {
@@ -378,6 +708,7 @@ impl Reader {
                                 func_error_context.clone(),
                                 ProcessingError::InvalidSymbolIndexError(
                                     usize::from(instr_index),
+                                    usize::from(u8::from(operand_index)),
                                     usize::from(sym_idx),
                                 ),
                             )
@@ -408,10 +739,13 @@ impl Reader {
 
                             symbol.value_idx = DataIdx::from(new_data_entry.1.get() - 1);
                         }
-                        let mut hasher = DefaultHasher::new();
-
-                        hasher.write(name.as_bytes());
-                        let name_hash = hasher.finish();
+                        let name_hash = if symbol.sym_bind == SymBind::Local {
+                            local_name_hash(name, file_hash)
+                        } else {
+                            let mut hasher = NameHasher::default();
+                            hasher.write(name.as_bytes());
+                            hasher.finish()
+                        };
 
                         let symbol_entry = SymbolEntry::new(name_hash, symbol, func_name_hash);
 
@@ -430,7 +764,41 @@ impl Reader {
                 }
             }
             None => {
-                Reader::data_tempop_from(func_error_context, data_index_map, instr_index, operand)?
+                let direct_sym_idx = SymbolIdx::from(usize::from(operand));
+
+                let is_direct_symref = resolve_direct_symrefs
+                    && !data_index_map.contains_key(&operand)
+                    && symtab
+                        .get(direct_sym_idx)
+                        .is_some_and(|symbol| symbol.sym_type != SymType::File);
+
+                if is_direct_symref {
+                    Reader::tempop_from(
+                        symtab,
+                        symstrtab,
+                        func_error_context,
+                        data_index_map,
+                        referenced_symbol_map,
+                        symbol_table,
+                        symbol_name_table,
+                        local_symbol_table,
+                        file_hash,
+                        func_name_hash,
+                        instr_index,
+                        operand_index,
+                        Some(direct_sym_idx),
+                        operand,
+                        resolve_direct_symrefs,
+                    )?
+                } else {
+                    Reader::data_tempop_from(
+                        func_error_context,
+                        data_index_map,
+                        instr_index,
+                        operand_index,
+                        operand,
+                    )?
+                }
             }
         })
     }
@@ -439,6 +807,7 @@ impl Reader {
         func_error_context: &FuncErrorContext,
         data_index_map: &HashMap<DataIdx, (u64, NonZeroUsize)>,
         instr_index: InstrIdx,
+        operand_index: OperandIndex,
         operand: DataIdx,
     ) -> LinkResult<TempOperand> {
         let data_result = *data_index_map.get(&operand).ok_or_else(|| {
@@ -446,6 +815,7 @@ impl Reader {
                 func_error_context.clone(),
                 ProcessingError::InvalidDataIndexError(
                     usize::from(instr_index),
+                    usize::from(u8::from(operand_index)),
                     usize::from(operand),
                 ),
             )
@@ -453,13 +823,16 @@ impl Reader {
         Ok(TempOperand::DataHash(data_result.0))
     }
 
-    fn process_relocations(
-        reld_section: &ReldSection,
-        reld_map: &mut HashMap<
-            SectionIdx,
-            HashMap<InstrIdx, (Option<SymbolIdx>, Option<SymbolIdx>)>,
-        >,
-    ) {
+    /// If the symbol table has a symbol that an instruction could only refer to through a
+    /// relocation. That is any external symbol, or any data symbol.
+    fn has_relocatable_symbols(symtab: &kerbalobjects::ko::sections::SymbolTable) -> bool {
+        symtab.symbols().any(|symbol| {
+            symbol.sym_bind == SymBind::Extern
+                || matches!(symbol.sym_type, SymType::NoType | SymType::Object)
+        })
+    }
+
+    fn process_relocations(reld_section: &ReldSection, reld_map: &mut ReldMap) {
         for entry in reld_section.entries() {
             match reld_map.get_mut(&entry.section_index) {
                 Some(func_map) => match func_map.get_mut(&entry.instr_index) {
@@ -0,0 +1,47 @@
+use serde::Serialize;
+
+/// A machine-readable description of the result of a link, intended for editors and build tools
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkSummary {
+    pub entry_point: String,
+    pub inputs: Vec<InputSummary>,
+    pub retained_functions: Vec<FunctionSummary>,
+    pub eliminated_functions: Vec<FunctionSummary>,
+    pub sections: SectionSizes,
+    /// The values in the argument section grouped by type, the most bytes first
+    pub argument_values: Vec<ValueTypeStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputSummary {
+    pub file_name: String,
+    pub source_file_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionSummary {
+    pub name: String,
+    pub file_name: String,
+    pub is_global: bool,
+    /// The absolute instruction index of the function in the main code section, if it was retained
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+}
+
+/// The size in bytes of each section of the output file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SectionSizes {
+    pub argument: usize,
+    pub function: usize,
+    pub initialization: usize,
+    pub main: usize,
+    pub debug: usize,
+}
+
+/// How many values of one type are in the argument section, and how many bytes they take up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValueTypeStats {
+    pub value_type: String,
+    pub count: usize,
+    pub bytes: usize,
+}
@@ -0,0 +1,86 @@
+use kerbalobjects::ksm::{Instr, KSMFile};
+use kerbalobjects::{KOSValue, Opcode};
+
+use super::errors::{LinkError, LinkResult};
+
+/// Checks that a linked KSM file is something kOS can actually run: every operand has to refer to
+/// a value in the argument section, and every label that is jumped or called to has to point to an
+/// instruction that exists. Other operands that only look like labels are data, and aren't checked.
+///
+/// This only finds bugs in the linker itself, any input that could cause them should have been
+/// rejected before this point.
+pub fn validate(ksm_file: &KSMFile) -> LinkResult<()> {
+    let instructions: Vec<&Instr> = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions())
+        .collect();
+
+    for (instr_index, instr) in instructions.iter().enumerate() {
+        let operands = match instr {
+            Instr::ZeroOp(_) => Vec::new(),
+            Instr::OneOp(_, op) => vec![*op],
+            Instr::TwoOp(_, op1, op2) => vec![*op1, *op2],
+        };
+
+        for (operand_index, op) in operands.into_iter().enumerate() {
+            let value = ksm_file.arg_section.get(op).ok_or_else(|| {
+                LinkError::ValidationError(format!(
+                    "instruction {} refers to argument index {}, which is not in the argument section",
+                    instr_index,
+                    usize::from(op)
+                ))
+            })?;
+
+            if !is_label_operand(instr.opcode(), operand_index + 1) {
+                continue;
+            }
+
+            if let Some(target) = label_target(value) {
+                if target == 0 || target >= instructions.len() {
+                    return Err(LinkError::ValidationError(format!(
+                        "instruction {} refers to label {}, but there are only {} instructions",
+                        instr_index,
+                        label(target),
+                        instructions.len()
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The label of the instruction at the given index. Like the labels kOS generates itself, the number
+/// is padded to four digits and simply grows longer once it reaches @10000.
+pub(crate) fn label(instr_index: usize) -> String {
+    format!("@{:0>4}", instr_index)
+}
+
+/// If the given operand of an instruction with this opcode, numbered from 1, is the label it jumps
+/// or calls to. kOS labels are ordinary strings, so a value that looks like one anywhere else, such
+/// as a pushed "@0003", is data. Setting the label with lbrt is where labels come from, so it
+/// doesn't point to anything.
+pub(crate) fn is_label_operand(opcode: Opcode, operand_index: usize) -> bool {
+    operand_index == 1
+        && matches!(
+            opcode,
+            Opcode::Jmp | Opcode::Bfa | Opcode::Btr | Opcode::Call | Opcode::Pdrl
+        )
+}
+
+/// The instruction a value points to if it is a label in the form the linker generates, `@NNNN`
+pub(crate) fn label_target(value: &KOSValue) -> Option<usize> {
+    match value {
+        KOSValue::String(s) => {
+            let digits = s.strip_prefix('@')?;
+
+            if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                digits.parse().ok()
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
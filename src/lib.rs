@@ -1,62 +1,423 @@
-use clap::Parser;
+use builder::CLIConfigBuilder;
+use clap::builder::ArgPredicate;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use config_file::ConfigFile;
+use driver::debug;
+use driver::errors::{LinkError, LinkResult};
+use driver::log::Reporter;
+use driver::reader::Reader;
+use driver::validate;
 use driver::Driver;
-use std::error::Error;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use kerbalobjects::ksm::KSMFile;
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::io::BufWriter;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use tables::ObjectData;
 
+pub mod builder;
+pub mod config_file;
 pub mod driver;
 
 pub mod tables;
 
 pub static VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub fn run(config: &CLIConfig) -> Result<(), Box<dyn Error>> {
-    let mut output_path = config.output_path.clone();
+/// Parses command line arguments into a config. Options that aren't given are read from the config
+/// file given by --config, or from kld.toml in the current directory if there is one, before falling
+/// back to their defaults.
+///
+/// Like clap's own parsing, this exits the process if the arguments themselves are invalid.
+pub fn parse_args<I, T>(args: I) -> LinkResult<CLIConfig>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = CLIConfig::command().get_matches_from(args);
+    let mut config = CLIConfig::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
-    if output_path.extension().is_none() {
-        output_path.set_extension(".ksm");
+    let config_path = match &config.config_file {
+        Some(config_path) => Some(config_path.to_owned()),
+        None => {
+            let default_path = PathBuf::from(config_file::DEFAULT_CONFIG_FILE);
+
+            default_path.is_file().then_some(default_path)
+        }
+    };
+
+    if let Some(config_path) = config_path {
+        ConfigFile::read(config_path)?.apply(&mut config, &matches);
+    }
+
+    Ok(config)
+}
+
+/// Links the inputs given by the config and writes the output, along with any other files asked for
+pub fn run(config: &CLIConfig) -> LinkResult<()> {
+    if config.list_sources {
+        for source in list_sources(&config.input_paths)? {
+            print!("{}", source);
+        }
+
+        return Ok(());
+    }
+
+    let mut output_path = config
+        .output_path
+        .clone()
+        .ok_or(LinkError::MissingOutputPathError)?;
+
+    // An output path of - means standard output, which shouldn't be given an extension
+    let to_stdout = config.writes_to_stdout();
+
+    if !to_stdout && output_path.extension().is_none() {
+        output_path.set_extension(config.output_format.extension());
     }
 
     let mut driver = Driver::new(config.to_owned());
 
-    for file_path in &config.input_paths {
-        driver.add(file_path);
+    for file_path in &expand_response_files(&config.input_paths)? {
+        // Archives only contribute the members that are actually needed
+        if file_path
+            .extension()
+            .is_some_and(|extension| extension == "kar")
+        {
+            driver.add_archive(file_path);
+        } else {
+            driver.add(file_path);
+        }
+    }
+
+    let link_result = driver.link();
+
+    let reporter = config.reporter();
+
+    // Warnings that --werror made into errors are listed by the error instead, which even --quiet
+    // prints
+    if !matches!(link_result, Err(LinkError::WarningsAsErrors(_))) {
+        for warning in driver.warnings() {
+            reporter.warning(warning);
+        }
+    }
+
+    let ksm_file = link_result?;
+
+    // The size can only be checked once the file is compressed, and a file that is too large
+    // shouldn't replace the old output, so only then is the whole file kept in memory
+    let file_buffer = match config.max_size {
+        Some(max_size) => {
+            let file_buffer = write_ksm(&ksm_file, config.ksm_magic);
+
+            if file_buffer.len() > max_size {
+                return Err(LinkError::OutputTooLargeError(
+                    file_buffer.len(),
+                    max_size,
+                    uncompressed_size(&ksm_file),
+                ));
+            }
+
+            Some(file_buffer)
+        }
+        None => None,
+    };
+
+    let write_output = |writer: &mut dyn Write| -> std::io::Result<()> {
+        match &file_buffer {
+            Some(file_buffer) => writer.write_all(file_buffer),
+            None => write_ksm_to(&ksm_file, config.ksm_magic, writer).map(|_| ()),
+        }
+    };
+
+    if to_stdout {
+        // Stdout is written as raw bytes, there is no line ending translation to corrupt the file
+        let mut stdout = std::io::stdout().lock();
+
+        write_output(&mut stdout)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| write_error(&output_path, e))?;
+    } else {
+        let file = std::fs::File::create(&output_path).map_err(|e| write_error(&output_path, e))?;
+        let mut writer = BufWriter::new(file);
+
+        write_output(&mut writer)
+            .and_then(|_| writer.flush())
+            .map_err(|e| write_error(&output_path, e))?;
     }
 
-    let ksm_file = driver.link()?;
+    if let (Some(json_path), Some(summary)) = (&config.emit_json, driver.summary()) {
+        let json_file = std::fs::File::create(json_path).map_err(|e| write_error(json_path, e))?;
 
-    let mut file_buffer = Vec::with_capacity(2048);
+        serde_json::to_writer_pretty(json_file, summary)
+            .map_err(|e| LinkError::SummaryWriteError(OsString::from(json_path.as_os_str()), e))?;
+    }
 
-    ksm_file.write(&mut file_buffer);
+    if let Some(exports_path) = &config.emit_exports {
+        let mut exports_file =
+            std::fs::File::create(exports_path).map_err(|e| write_error(exports_path, e))?;
 
-    let mut file = std::fs::File::create(output_path)?;
+        let function_offsets = driver.function_offsets();
 
-    file.write_all(file_buffer.as_slice())?;
+        for name in driver.exports() {
+            // A hybrid output is loaded as a library by running it, so functions are found by label
+            match function_offsets.get(&name) {
+                Some(offset) if config.hybrid => {
+                    writeln!(exports_file, "{}\t{}", name, validate::label(*offset))
+                }
+                _ => writeln!(exports_file, "{}", name),
+            }
+            .map_err(|e| write_error(exports_path, e))?;
+        }
+    }
+
+    if let Some(debug_path) = &config.emit_debug {
+        std::fs::write(debug_path, debug::debug_map(driver.source_map()))
+            .map_err(|e| write_error(debug_path, e))?;
+    }
 
     Ok(())
 }
 
+/// Reads and processes a single KO file without linking it, so that tools can see which symbols and
+/// functions it provides. Gzipped files are decompressed first.
+///
+/// This is the same processing the driver does for each input, see Reader::process_file.
+pub fn inspect_object(path: impl Into<PathBuf>) -> LinkResult<ObjectData> {
+    let (file_name, kofile) = Reader::read_file(path)?;
+
+    Reader::process_file(file_name, kofile, false, false)
+}
+
+/// Where a KO file came from, as listed by --list-sources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectSource {
+    pub input_file_name: String,
+    /// The name of the KO file's file symbol
+    pub source_file_name: String,
+    pub comment: Option<String>,
+}
+
+impl Display for ObjectSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", self.input_file_name, self.source_file_name)?;
+
+        if let Some(comment) = &self.comment {
+            for line in comment.lines() {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the source file and comment of every input without processing or linking them, which is
+/// enough to find out what produced each one. Response files are expanded the same as for a link.
+pub fn list_sources(input_paths: &[PathBuf]) -> LinkResult<Vec<ObjectSource>> {
+    expand_response_files(input_paths)?
+        .into_iter()
+        .map(|path| {
+            let (file_name, kofile) = Reader::read_file(path)?;
+
+            Ok(ObjectSource {
+                source_file_name: Reader::source_file_name(&file_name, &kofile)?,
+                comment: Reader::comment(&kofile),
+                input_file_name: file_name,
+            })
+        })
+        .collect()
+}
+
+/// Replaces every response file argument, one that starts with @, with the input paths listed in it.
+///
+/// A response file has one path per line, and blank lines and lines starting with # are ignored.
+/// Relative paths are relative to the directory the response file is in.
+fn expand_response_files(input_paths: &[PathBuf]) -> LinkResult<Vec<PathBuf>> {
+    let mut expanded = Vec::with_capacity(input_paths.len());
+
+    for input_path in input_paths {
+        let response_path = match input_path.to_str().and_then(|path| path.strip_prefix('@')) {
+            Some(response_path) => Path::new(response_path),
+            None => {
+                expanded.push(input_path.to_owned());
+                continue;
+            }
+        };
+
+        let contents = std::fs::read_to_string(response_path)
+            .map_err(|e| LinkError::IOError(OsString::from(response_path.as_os_str()), e.kind()))?;
+
+        let base_dir = response_path.parent().unwrap_or(Path::new(""));
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            expanded.push(base_dir.join(line));
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn write_error(path: &Path, error: std::io::Error) -> LinkError {
+    LinkError::FileWriteError(OsString::from(path.as_os_str()), error.kind())
+}
+
+/// Writes a linked KSM file. An experimental magic number can be given to replace the standard one,
+/// but kOS itself will refuse to load the file if it is.
+pub fn write_ksm(ksm_file: &KSMFile, magic: Option<u32>) -> Vec<u8> {
+    write_ksm_to(ksm_file, magic, Vec::with_capacity(2048)).expect("Error compressing KSM file")
+}
+
+/// Writes a linked KSM file to anything that can be written to, such as a file, returning it after
+/// the compressed contents are finished.
+///
+/// Unlike KSMFile::write, the whole file is never in memory at once. Each section is compressed as
+/// soon as it is converted to bytes, so only the largest section and the compressor's own buffers
+/// are.
+pub fn write_ksm_to<W: Write>(
+    ksm_file: &KSMFile,
+    magic: Option<u32>,
+    writer: W,
+) -> std::io::Result<W> {
+    let mut encoder = GzEncoder::new(writer, Compression::best());
+    let mut section_buffer = Vec::with_capacity(2048);
+
+    ksm_file.header.write(&mut section_buffer);
+
+    if let Some(magic) = magic {
+        section_buffer[..4].copy_from_slice(&magic.to_le_bytes());
+    }
+
+    ksm_file.arg_section.write(&mut section_buffer);
+    encoder.write_all(&section_buffer)?;
+
+    let index_bytes = ksm_file.arg_section.num_index_bytes();
+
+    for code_section in ksm_file.code_sections() {
+        section_buffer.clear();
+        code_section.write(&mut section_buffer, index_bytes);
+        encoder.write_all(&section_buffer)?;
+    }
+
+    section_buffer.clear();
+    ksm_file.debug_section.write(&mut section_buffer);
+    encoder.write_all(&section_buffer)?;
+
+    encoder.finish()
+}
+
+/// Merges an add-on KSM file into a base one that have both already been linked, such as for a
+/// plugin system. Fails if both have code in their main section, such as an entry point.
+///
+/// See driver::merge::merge for how the files are combined.
+pub fn merge_ksm(base: KSMFile, addon: KSMFile) -> LinkResult<KSMFile> {
+    driver::merge::merge(base, addon, false)
+}
+
+/// Merges an add-on KSM file into a base one, even if both have code in their main section. The
+/// base's main code is the one that runs.
+pub fn merge_ksm_keeping_entries(base: KSMFile, addon: KSMFile) -> LinkResult<KSMFile> {
+    driver::merge::merge(base, addon, true)
+}
+
+/// Parses a KSM magic number, which is usually written in hexadecimal
+fn parse_magic(value: &str) -> Result<u32, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    parsed.map_err(|e| format!("invalid magic number {}: {}", value, e))
+}
+
+/// The size of a KSM file before it is compressed, which is what kOS actually loads
+fn uncompressed_size(ksm_file: &KSMFile) -> usize {
+    let index_bytes = ksm_file.arg_section.num_index_bytes();
+
+    ksm_file.header.size_bytes()
+        + ksm_file.arg_section.size_bytes()
+        + ksm_file
+            .code_sections()
+            .map(|code_section| code_section.size_bytes(index_bytes))
+            .sum::<usize>()
+        + ksm_file.debug_section.size_bytes()
+}
+
+/// The kind of file that the linker produces. Merged KerbalObject output would need relocatable
+/// linking, which the driver can't do, so KSM is the only one for now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// A KerboScript Machine Code file that can be run by kOS
+    #[default]
+    Ksm,
+}
+
+impl OutputFormat {
+    /// The file extension used when the output path doesn't already have one
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Ksm => "ksm",
+        }
+    }
+}
+
 /// This structure controls all the settings that make this program perform differently
 /// These represent command-line arguments read in by clap
 #[derive(Debug, Clone, Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(arg(
+    clap::Arg::new("debug")
+        .short('d')
+        .long("debug")
+        .action(ArgAction::SetTrue)
+        .help("Logs everything, the same as -vvv")
+))]
 pub struct CLIConfig {
-    /// All of the input file paths, at least 1 is required.
+    /// All of the input file paths, at least 1 is required. A path starting with @ is a response file
+    /// that lists more input paths.
     #[arg(
         value_name = "INPUT",
-        help = "Sets the input path(s) to kld",
+        help = "Sets the input path(s) to kld. @FILE reads more input paths from FILE, one per line",
         required = true,
         num_args = 1..
     )]
     pub input_paths: Vec<PathBuf>,
-    /// The required output path. Extension optional.
+    /// The required output path. Extension optional. A path of - writes to standard output. Not
+    /// needed when only listing sources.
     #[arg(
         value_name = "OUTPUT",
         short = 'o',
         long = "output",
-        help = "The output file path"
+        required_unless_present = "list_sources",
+        help = "The output file path, or - to write to standard output"
+    )]
+    pub output_path: Option<PathBuf>,
+    /// The kind of file to produce, which also decides the default output extension
+    #[arg(
+        long = "oformat",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = OutputFormat::Ksm,
+        help = "The format of the output file"
+    )]
+    pub output_format: OutputFormat,
+    /// A file of default options, which anything given on the command line overrides. If this
+    /// isn't given, kld.toml in the current directory is read if there is one.
+    #[arg(
+        long = "config",
+        value_name = "FILE",
+        help = "Reads default options from the given file instead of kld.toml. Options given on the command line override it"
     )]
-    pub output_path: PathBuf,
+    pub config_file: Option<PathBuf>,
     /// A custom entry-point for the KSM program. Defaults to _start
     #[arg(
         short = 'e',
@@ -67,6 +428,14 @@ pub struct CLIConfig {
         help = "The name of the function that the program should begin execution in"
     )]
     pub entry_point: String,
+    /// How many instructions into the entry point execution should begin
+    #[arg(
+        long = "entry-offset",
+        value_name = "N",
+        default_value_t = 0,
+        help = "Begins execution the given number of instructions into the entry point instead of at its start"
+    )]
+    pub entry_offset: usize,
     /// If the output should be a "shared library" version of a KSM file
     #[arg(
         short = 's',
@@ -74,11 +443,272 @@ pub struct CLIConfig {
         help = "Will link the object files into a shared object file instead of being linked into an executable file"
     )]
     pub shared: bool,
-    /// Outputs a log of debugging information, mostly for the developers of this tool
+    /// If the output should be an executable that also keeps and exports every global function
+    #[arg(
+        long = "hybrid",
+        conflicts_with = "shared",
+        help = "Links an executable that also keeps every global function, so that it can be loaded as a library. --emit-exports lists the label each function starts at"
+    )]
+    pub hybrid: bool,
+    /// Input files whose global functions are all kept, even if they are never referenced
+    #[arg(
+        long = "whole-archive",
+        value_name = "FILE",
+        help = "Keeps every global function from the given input file, even if it is never referenced"
+    )]
+    pub whole_archive: Vec<PathBuf>,
+    /// Global functions that are kept even if nothing references them
+    #[arg(
+        long = "keep",
+        value_name = "NAME",
+        help = "Keeps the given global function even if it is never referenced, for functions only called indirectly or by kOS. Can be given more than once"
+    )]
+    pub keep_symbols: Vec<String>,
+    /// Directories that inputs not found at their given path are looked for in, in order
+    #[arg(
+        short = 'L',
+        long = "search-path",
+        value_name = "DIR",
+        help = "Looks for input files that aren't found at their given path in this directory. Can be given more than once"
+    )]
+    pub search_paths: Vec<PathBuf>,
+    /// If the same input path can be linked more than once
+    #[arg(
+        long = "allow-duplicate-inputs",
+        help = "Links an input file again each time it is listed, instead of only the first time"
+    )]
+    pub allow_duplicate_inputs: bool,
+    /// If identical copies of a function from different object files should be merged into one
+    #[arg(
+        long = "fold-identical",
+        help = "Merges functions with the same name and identical instructions into a single copy instead of reporting duplicates"
+    )]
+    pub fold_identical: bool,
+    /// The most instructions, not counting the return, that a function can have to be inlined
+    #[arg(
+        long = "inline-threshold",
+        value_name = "N",
+        help = "EXPERIMENTAL: Replaces calls to functions with at most N instructions before their return with the instructions themselves. Only functions that don't call or jump anywhere are inlined"
+    )]
+    pub inline_threshold: Option<usize>,
+    /// If a later global definition replaces an earlier one with the same name instead of being an error
+    #[arg(
+        long = "allow-multiple-definition",
+        help = "Keeps the last definition of a symbol that is defined more than once, instead of reporting duplicates"
+    )]
+    pub allow_multiple_definition: bool,
+    /// If _init and helper functions should be placed in their own code sections instead of main
+    #[arg(
+        long = "split-sections",
+        help = "Places _init in the initialization code section and all other functions except the entry point in the function code section"
+    )]
+    pub split_sections: bool,
+    /// If functions should be laid out sorted by name instead of in the order they were found.
+    /// This changes every function's offset, so it is for comparing outputs, not for speed.
     #[arg(
-        short = 'd',
-        long = "debug",
-        help = "Outputs a log of debugging information, mostly for the developers of this tool"
+        long = "sort-functions",
+        help = "Lays out functions sorted by name after _init and the entry point, so that outputs can be diffed between builds"
+    )]
+    pub sort_functions: bool,
+    /// A file listing functions that should be laid out first, in the order they are listed
+    #[arg(
+        long = "layout",
+        value_name = "FILE",
+        help = "Lays out the functions listed in the given file, one name per line, in that order after _init and the entry point. Every other function comes after them"
+    )]
+    pub layout: Option<PathBuf>,
+    /// The number of instructions each function's offset should be a multiple of
+    #[arg(
+        long = "align-functions",
+        value_name = "N",
+        help = "Pads the code with Nop instructions so that every function starts at a multiple of N instructions"
+    )]
+    pub align_functions: Option<NonZeroUsize>,
+    /// If operands with no relocation entry may refer to the symbol table directly
+    #[arg(
+        long = "resolve-direct-symrefs",
+        help = "Treats an operand with no relocation entry as a symbol table index if it isn't a valid data index, for object files from other assemblers"
+    )]
+    pub resolve_direct_symrefs: bool,
+    /// If a file whose functions reference symbols has to have a .reld section
+    #[arg(
+        long = "require-reld",
+        help = "Fails the link if a file has functions and symbols they could reference, but no .reld section. Has no effect with --resolve-direct-symrefs"
+    )]
+    pub require_reld: bool,
+    /// A comment to put in the output instead of the entry point's comment
+    #[arg(
+        long = "comment",
+        value_name = "TEXT",
+        help = "Uses the given text as the output's comment instead of the entry point file's comment"
+    )]
+    pub comment: Option<String>,
+    /// The input file whose comment is put in the output instead of the entry point's comment
+    #[arg(
+        long = "comment-from",
+        value_name = "FILE",
+        conflicts_with = "comment",
+        help = "Uses the comment of the given input file instead of the entry point file's comment"
+    )]
+    pub comment_from: Option<PathBuf>,
+    /// Where to write a JSON summary of the link, if anywhere
+    #[arg(
+        long = "emit-json",
+        value_name = "PATH",
+        help = "Writes a JSON summary of the link to the given path, for use by other tools"
+    )]
+    pub emit_json: Option<PathBuf>,
+    /// Where to write the list of symbols the output exports, if anywhere
+    #[arg(
+        long = "emit-exports",
+        value_name = "PATH",
+        help = "Writes the names of every global symbol in the output to the given path, one per line"
+    )]
+    pub emit_exports: Option<PathBuf>,
+    /// Where to write which function and source file each instruction came from, if anywhere
+    #[arg(
+        long = "emit-debug",
+        value_name = "PATH",
+        help = "Writes which instructions each function takes up and which source file it came from to the given path. The output itself is unchanged"
+    )]
+    pub emit_debug: Option<PathBuf>,
+    /// Global symbols that shouldn't be exported from a shared object. Every other global is.
+    #[arg(
+        long = "hide-symbol",
+        value_name = "NAME",
+        help = "Keeps the given global symbol out of a shared or hybrid object's exports, while still allowing it to be used inside of it"
     )]
-    pub debug: bool,
+    pub hide_symbols: Vec<String>,
+    /// Symbols whose references go to __wrap_NAME instead, with __real_NAME reaching the original
+    #[arg(
+        long = "wrap",
+        value_name = "NAME",
+        help = "Redirects references to NAME to __wrap_NAME, and references to __real_NAME to NAME. Can be given more than once"
+    )]
+    pub wrap_symbols: Vec<String>,
+    /// If every declared external symbol has to be defined, even ones that are never used
+    #[arg(
+        long = "no-undefined",
+        help = "Fails the link if any external symbol is never defined, even if nothing uses it. Has no effect on shared objects"
+    )]
+    pub no_undefined: bool,
+    /// If retained global functions should be checked against the names of kOS built-ins
+    #[arg(
+        long = "warn-builtins",
+        help = "Warns about every global function that has the same name as a kOS built-in function, such as print or stage"
+    )]
+    pub warn_builtins: bool,
+    /// If the symbol table should be printed once every symbol has been resolved
+    #[arg(
+        long = "print-symbols",
+        help = "Prints every symbol after resolution with its binding, type, and file, sorted by name"
+    )]
+    pub print_symbols: bool,
+    /// If the tree of why each function was kept should be printed
+    #[arg(
+        long = "gc-sections-print-graph",
+        help = "Prints which function reached each function that was kept, as a tree under _init, _start, and any other root"
+    )]
+    pub print_gc_graph: bool,
+    /// If each global symbol in the output should be printed as exported or hidden
+    #[arg(
+        long = "print-exports",
+        help = "Prints every global symbol in the output sorted by name, labelled EXPORTED if another program can import it or HIDDEN if --hide-symbol keeps it internal"
+    )]
+    pub print_exports: bool,
+    /// If how many values of each type are in the argument section should be printed
+    #[arg(
+        long = "stats",
+        help = "Prints how many values of each type are in the output's argument section, and how many bytes they take up"
+    )]
+    pub print_stats: bool,
+    /// Symbols to report on every time they are seen while resolving symbols
+    #[arg(
+        short = 'y',
+        long = "trace-symbol",
+        value_name = "NAME",
+        help = "Prints each input that references or defines the given symbol, and which definition is used"
+    )]
+    pub trace_symbols: Vec<String>,
+    /// If the source file and comment of each input should be printed instead of linking them
+    #[arg(
+        long = "list-sources",
+        help = "Prints the source file and comment of each input instead of linking them"
+    )]
+    pub list_sources: bool,
+    /// If the output should be checked for internal linker errors before it is written
+    #[arg(
+        long = "validate",
+        help = "Checks that every operand and label in the output is valid before writing it, to catch bugs in the linker"
+    )]
+    pub validate: bool,
+    /// The largest the output file may be, in bytes
+    #[arg(
+        long = "max-size",
+        value_name = "BYTES",
+        help = "Fails the link if the output file would be larger than the given number of bytes"
+    )]
+    pub max_size: Option<usize>,
+    /// If a warning should be emitted when operands need more than one byte to index the argument section
+    #[arg(
+        long = "warn-addr-width",
+        help = "Warns when the argument section grows large enough that every operand takes more than one byte"
+    )]
+    pub warn_addr_width: bool,
+    /// The number of bytes every operand should take, if more than the argument section needs
+    #[arg(
+        long = "addr-width",
+        value_name = "BYTES",
+        value_parser = clap::value_parser!(u8).range(1..=4),
+        help = "Makes every operand take the given number of bytes, from 1 to 4, by padding the argument section. Fails if the argument section needs more. The padding is loaded by kOS like any other value: 3 adds about 64 KiB, and 4 adds about 1.6 MiB"
+    )]
+    pub addr_width: Option<u8>,
+    /// A magic number to write in place of the standard KSM magic, for experimental runtimes
+    #[arg(
+        long = "ksm-magic",
+        value_name = "MAGIC",
+        value_parser = parse_magic,
+        help = "EXPERIMENTAL: Writes the given magic number, such as 0x4558036b, in the KSM header instead of the standard one. kOS will not load the output"
+    )]
+    pub ksm_magic: Option<u32>,
+    /// If any warnings should cause the link to fail
+    #[arg(long = "werror", help = "Treats all warnings as errors")]
+    pub werror: bool,
+    /// How much information to log while linking, from 0 (nothing) to 3 (everything)
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        action = ArgAction::Count,
+        conflicts_with = "debug",
+        default_value_if("debug", ArgPredicate::IsPresent, Some("3")),
+        help = "Logs information about the link, more of it each time this is repeated (up to -vvv)"
+    )]
+    pub verbosity: u8,
+    /// If nothing but errors should be printed
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        conflicts_with_all = ["verbosity", "debug"],
+        help = "Prints nothing but errors, not even warnings or reports such as --print-symbols. Warnings still fail the link with --werror"
+    )]
+    pub quiet: bool,
+}
+
+impl CLIConfig {
+    /// Starts building a config in code, with every setting at its default
+    pub fn builder(output_path: impl Into<PathBuf>) -> CLIConfigBuilder {
+        CLIConfigBuilder::new(output_path)
+    }
+
+    /// If the output is written to stdout, in which case nothing else can be printed there
+    pub fn writes_to_stdout(&self) -> bool {
+        self.output_path
+            .as_ref()
+            .is_some_and(|output_path| output_path.as_os_str() == "-")
+    }
+
+    /// Where warnings, logs, and reports are printed for this config
+    pub fn reporter(&self) -> Reporter {
+        Reporter::new(self.verbosity, self.quiet, self.writes_to_stdout())
+    }
 }
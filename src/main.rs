@@ -1,12 +1,11 @@
-use clap::Parser;
 use std::process;
 
-use klinker::{run, CLIConfig};
+use klinker::{parse_args, run};
 
 fn main() {
-    let config = CLIConfig::parse();
+    let result = parse_args(std::env::args_os()).and_then(|config| run(&config));
 
-    if let Err(e) = run(&config) {
+    if let Err(e) = result {
         eprintln!("{}", e);
 
         process::exit(1);
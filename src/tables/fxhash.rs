@@ -0,0 +1,75 @@
+use std::hash::Hasher;
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The hasher used by rustc, which is much faster than SipHash for short keys such as symbol
+/// names, but makes no attempt to resist collisions that are made on purpose.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+
+        for chunk in chunks.by_ref() {
+            // chunks_exact only gives out slices of exactly 8 bytes
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        let mut remainder = chunks.remainder();
+
+        if remainder.len() >= 4 {
+            self.add_to_hash(u32::from_le_bytes(remainder[..4].try_into().unwrap()) as u64);
+            remainder = &remainder[4..];
+        }
+
+        if remainder.len() >= 2 {
+            self.add_to_hash(u16::from_le_bytes(remainder[..2].try_into().unwrap()) as u64);
+            remainder = &remainder[2..];
+        }
+
+        if let Some(byte) = remainder.first() {
+            self.add_to_hash(*byte as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(not(feature = "fxhash"))]
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
@@ -7,29 +8,38 @@ use std::vec::Drain;
 
 use kerbalobjects::{ko::symbols::KOSymbol, KOSValue, Opcode};
 
+#[cfg(feature = "fxhash")]
+mod fxhash;
 mod nametables;
 pub use nametables::*;
 
+/// The hasher that every name and data value is hashed with. Hashes are only ever compared with
+/// others from the same link, so which one is used can change freely.
+#[cfg(not(feature = "fxhash"))]
+pub type NameHasher = DefaultHasher;
+#[cfg(feature = "fxhash")]
+pub type NameHasher = fxhash::FxHasher;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
 pub enum ContextHash {
     FuncNameHash(u64),
     FileNameHash(u64),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TempOperand {
     DataHash(u64),
     SymNameHash(u64),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TempInstr {
     ZeroOp(Opcode),
     OneOp(Opcode, TempOperand),
     TwoOp(Opcode, TempOperand, TempOperand),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ObjectData {
     pub input_file_name: String,
     pub source_file_name: String,
@@ -39,27 +49,52 @@ pub struct ObjectData {
     pub function_table: FunctionTable,
     pub symbol_table: SymbolTable,
     pub data_table: DataTable,
+    // The hash of input_file_name, which is part of the hash of every local symbol and function
+    pub file_name_hash: u64,
     pub local_function_table: FunctionTable,
     pub local_symbol_table: SymbolTable,
     pub local_function_hash_map: HashMap<u64, usize>,
     pub local_function_name_table: NameTable<NonZeroUsize>,
     pub local_function_ref_vec: Vec<u64>,
+    // The names of external symbols that are declared but never used by any instruction
+    pub unreferenced_externs: Vec<String>,
+}
+
+impl ObjectData {
+    /// The hash a local symbol or function with this name has in this file
+    pub fn local_hash(&self, name: &str) -> u64 {
+        local_name_hash(name, self.file_name_hash)
+    }
+}
+
+/// Hashes the name of a local symbol or function together with the file it is in.
+///
+/// Locals can only be used from inside of their own file, so two files can each have a local with
+/// the same name. Including the file keeps every local's hash unique, and different from the hash
+/// of any global with that name.
+pub fn local_name_hash(name: &str, file_name_hash: u64) -> u64 {
+    let mut hasher = NameHasher::default();
+    hasher.write(name.as_bytes());
+    hasher.write_u64(file_name_hash);
+    hasher.finish()
 }
 
 #[derive(Debug, Clone)]
 pub struct Function {
     object_data_index: usize,
+    // The index of the section this function came from, which is its position in the source file
+    section_index: u16,
     name_hash: u64,
     is_global: bool,
     instructions: Vec<TempInstr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FunctionTable {
     entries: Vec<Function>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolEntry {
     name_hash: u64,
     symbol: KOSymbol,
@@ -68,18 +103,22 @@ pub struct SymbolEntry {
 
 #[derive(Debug)]
 pub struct MasterSymbolEntry {
+    object_data_index: usize,
     symbol: KOSymbol,
     ctx: ContextHash,
+    hidden: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolTable {
     entries: Vec<SymbolEntry>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DataTable {
     hashes: Vec<u64>,
+    // Maps each hash to its position so that lookups don't have to search the whole table
+    positions: HashMap<u64, usize>,
     data: Vec<KOSValue>,
 }
 
@@ -87,6 +126,7 @@ impl Function {
     pub fn new(name_hash: u64, is_global: bool) -> Self {
         Function {
             object_data_index: 0,
+            section_index: 0,
             name_hash,
             is_global,
             instructions: Vec::new(),
@@ -96,6 +136,7 @@ impl Function {
     pub fn with_capacity(name_hash: u64, is_global: bool, capacity: usize) -> Self {
         Function {
             object_data_index: 0,
+            section_index: 0,
             name_hash,
             is_global,
             instructions: Vec::with_capacity(capacity),
@@ -114,10 +155,14 @@ impl Function {
         self.instructions.push(instr);
     }
 
-    pub fn instructions(&self) -> Iter<TempInstr> {
+    pub fn instructions(&self) -> Iter<'_, TempInstr> {
         self.instructions.iter()
     }
 
+    pub fn instructions_mut(&mut self) -> IterMut<'_, TempInstr> {
+        self.instructions.iter_mut()
+    }
+
     pub fn drain(&mut self) -> Vec<TempInstr> {
         self.instructions.drain(..).collect()
     }
@@ -126,6 +171,19 @@ impl Function {
         self.instructions.len()
     }
 
+    /// Hashes this function's instructions, but not its name or where it came from.
+    ///
+    /// Operands are hashed by what they refer to rather than by their data index in the object
+    /// file: values by their contents, and global symbols by their names. So two functions with
+    /// the same code hash equally even if they are from different files. References to local
+    /// symbols include the file they are in, so functions that use them only hash equally with
+    /// functions from the same file.
+    pub fn structural_hash(&self) -> u64 {
+        let mut hasher = NameHasher::default();
+        self.instructions.hash(&mut hasher);
+        hasher.finish()
+    }
+
     pub fn set_object_data_index(&mut self, index: usize) {
         self.object_data_index = index;
     }
@@ -133,6 +191,14 @@ impl Function {
     pub fn object_data_index(&self) -> usize {
         self.object_data_index
     }
+
+    pub fn set_section_index(&mut self, index: u16) {
+        self.section_index = index;
+    }
+
+    pub fn section_index(&self) -> u16 {
+        self.section_index
+    }
 }
 
 impl FunctionTable {
@@ -146,11 +212,11 @@ impl FunctionTable {
         self.entries.push(func);
     }
 
-    pub fn functions(&self) -> Iter<Function> {
+    pub fn functions(&self) -> Iter<'_, Function> {
         self.entries.iter()
     }
 
-    pub fn functions_mut(&mut self) -> IterMut<Function> {
+    pub fn functions_mut(&mut self) -> IterMut<'_, Function> {
         self.entries.iter_mut()
     }
 
@@ -161,6 +227,21 @@ impl FunctionTable {
     pub fn get_by_hash(&self, hash: u64) -> Option<&Function> {
         self.entries.iter().find(|func| func.name_hash == hash)
     }
+
+    pub fn remove_by_hash(&mut self, hash: u64) -> Option<Function> {
+        let position = self
+            .entries
+            .iter()
+            .position(|func| func.name_hash == hash)?;
+
+        Some(self.entries.remove(position))
+    }
+}
+
+impl Default for FunctionTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SymbolEntry {
@@ -176,6 +257,10 @@ impl SymbolEntry {
         self.name_hash
     }
 
+    pub fn set_name_hash(&mut self, new: u64) {
+        self.name_hash = new;
+    }
+
     pub fn internal(&self) -> &KOSymbol {
         &self.symbol
     }
@@ -201,7 +286,12 @@ impl From<SymbolEntry> for KOSymbol {
 
 impl MasterSymbolEntry {
     pub fn new(symbol: KOSymbol, ctx: ContextHash) -> Self {
-        MasterSymbolEntry { symbol, ctx }
+        MasterSymbolEntry {
+            object_data_index: 0,
+            symbol,
+            ctx,
+            hidden: false,
+        }
     }
 
     pub fn internal(&self) -> &KOSymbol {
@@ -215,13 +305,32 @@ impl MasterSymbolEntry {
     pub fn context(&self) -> ContextHash {
         self.ctx
     }
+
+    pub fn set_object_data_index(&mut self, index: usize) {
+        self.object_data_index = index;
+    }
+
+    pub fn object_data_index(&self) -> usize {
+        self.object_data_index
+    }
+
+    /// Hidden symbols can still be referenced by any file in the link, but are not exported
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
 }
 
 impl From<SymbolEntry> for MasterSymbolEntry {
     fn from(entry: SymbolEntry) -> Self {
         MasterSymbolEntry {
+            object_data_index: 0,
             symbol: entry.symbol,
             ctx: entry.ctx,
+            hidden: false,
         }
     }
 }
@@ -240,11 +349,15 @@ impl SymbolTable {
         unsafe { NonZeroUsize::new_unchecked(self.entries.len()) }
     }
 
-    pub fn symbols(&self) -> Iter<SymbolEntry> {
+    pub fn symbols(&self) -> Iter<'_, SymbolEntry> {
         self.entries.iter()
     }
 
-    pub fn drain(&mut self) -> Drain<SymbolEntry> {
+    pub fn symbols_mut(&mut self) -> IterMut<'_, SymbolEntry> {
+        self.entries.iter_mut()
+    }
+
+    pub fn drain(&mut self) -> Drain<'_, SymbolEntry> {
         self.entries.drain(..)
     }
 
@@ -252,25 +365,34 @@ impl SymbolTable {
         self.entries.iter().find(|sym| sym.name_hash == hash)
     }
 }
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DataTable {
     pub fn new() -> Self {
         DataTable {
             hashes: Vec::new(),
+            positions: HashMap::new(),
             data: Vec::new(),
         }
     }
 
     pub fn add(&mut self, value: KOSValue) -> (u64, NonZeroUsize) {
-        let mut hasher = DefaultHasher::new();
+        let mut hasher = NameHasher::default();
         value.hash(&mut hasher);
         let hash = hasher.finish();
 
         (
             hash,
-            match self.hashes.iter().position(|item| *item == hash) {
+            match self.positions.get(&hash) {
                 // SAFETY: This is safe because we add 1 to it unconditionally
                 Some(pos) => unsafe { NonZeroUsize::new_unchecked(pos + 1) },
                 None => {
+                    self.positions.insert(hash, self.hashes.len());
                     self.hashes.push(hash);
                     self.data.push(value);
                     // SAFETY: This is safe because it is after we just added an item, it will always be >= 1
@@ -285,21 +407,19 @@ impl DataTable {
     }
 
     pub fn get_by_hash(&self, hash: u64) -> Option<&KOSValue> {
-        match self.hashes.iter().position(|item| item == &hash) {
-            Some(pos) => self.data.get(pos),
-            None => None,
-        }
+        let pos = self.positions.get(&hash)?;
+        self.data.get(*pos)
     }
 
     pub fn hash_at(&self, index: NonZeroUsize) -> Option<&u64> {
         self.hashes.get(index.get() - 1)
     }
 
-    pub fn entries(&self) -> Iter<KOSValue> {
+    pub fn entries(&self) -> Iter<'_, KOSValue> {
         self.data.iter()
     }
 
-    pub fn hashes(&self) -> Iter<u64> {
+    pub fn hashes(&self) -> Iter<'_, u64> {
         self.hashes.iter()
     }
 
@@ -313,3 +433,9 @@ impl DataTable {
         size
     }
 }
+
+impl Default for DataTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
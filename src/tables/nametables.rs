@@ -1,5 +1,8 @@
+use std::collections::HashMap;
 use std::slice::{Iter, IterMut};
-use std::{collections::hash_map::DefaultHasher, hash::Hasher, num::NonZeroUsize};
+use std::{hash::Hasher, num::NonZeroUsize};
+
+use super::NameHasher;
 
 #[derive(Debug, Clone)]
 pub struct NameTableEntry<T> {
@@ -7,9 +10,11 @@ pub struct NameTableEntry<T> {
     value: T,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NameTable<T> {
     hashes: Vec<u64>,
+    // Maps each hash to its position so that lookups don't have to search the whole table
+    positions: HashMap<u64, NonZeroUsize>,
     entries: Vec<NameTableEntry<T>>,
     size: usize,
 }
@@ -42,6 +47,7 @@ impl<T> NameTable<T> {
     pub fn new() -> Self {
         NameTable {
             hashes: Vec::new(),
+            positions: HashMap::new(),
             entries: Vec::new(),
             size: 0,
         }
@@ -50,11 +56,13 @@ impl<T> NameTable<T> {
     pub fn with_capacity(capacity: usize) -> Self {
         NameTable {
             hashes: Vec::with_capacity(capacity),
+            positions: HashMap::with_capacity(capacity),
             entries: Vec::with_capacity(capacity),
             size: 0,
         }
     }
 
+    #[allow(clippy::result_unit_err)]
     pub fn replace_at(&mut self, index: NonZeroUsize, new_value: T) -> Result<(), ()> {
         let entry = self.entries.get_mut(index.get() - 1).ok_or(())?;
 
@@ -63,6 +71,7 @@ impl<T> NameTable<T> {
         Ok(())
     }
 
+    #[allow(clippy::result_unit_err)]
     pub fn replace_by_hash(&mut self, hash: u64, new_value: T) -> Result<(), ()> {
         let entry = self.get_mut_by_hash(hash).ok_or(())?;
 
@@ -71,25 +80,32 @@ impl<T> NameTable<T> {
         Ok(())
     }
 
-    pub fn raw_insert(&mut self, hash: u64, entry: NameTableEntry<T>) -> NonZeroUsize {
+    /// Inserts an entry under the given hash, unless one is already there. Returns the entry's
+    /// position, and true if it was newly inserted.
+    pub fn raw_insert(&mut self, hash: u64, entry: NameTableEntry<T>) -> (NonZeroUsize, bool) {
         match self.position_by_hash(hash) {
-            Some(pos) => pos,
+            Some(pos) => (pos, false),
             None => {
                 self.hashes.push(hash);
                 self.entries.push(entry);
                 self.size += 1;
 
                 // SAFETY: This is safe because the "real" index is always equal to the size plus 1
-                unsafe { NonZeroUsize::new_unchecked(self.size) }
+                let position = unsafe { NonZeroUsize::new_unchecked(self.size) };
+                self.positions.insert(hash, position);
+
+                (position, true)
             }
         }
     }
 
-    pub fn insert(&mut self, entry: NameTableEntry<T>) -> NonZeroUsize {
+    /// Inserts an entry, unless one with the same name is already there. Returns the entry's
+    /// position, and true if it was newly inserted.
+    pub fn insert(&mut self, entry: NameTableEntry<T>) -> (NonZeroUsize, bool) {
         match self.position(&entry.name) {
-            Some(pos) => pos,
+            Some(pos) => (pos, false),
             None => {
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = NameHasher::default();
                 hasher.write(entry.name.as_bytes());
 
                 let hash = hasher.finish();
@@ -99,7 +115,10 @@ impl<T> NameTable<T> {
                 self.size += 1;
 
                 // SAFETY: This is safe because the "real" index is always equal to the size plus 1
-                unsafe { NonZeroUsize::new_unchecked(self.size) }
+                let position = unsafe { NonZeroUsize::new_unchecked(self.size) };
+                self.positions.insert(hash, position);
+
+                (position, true)
             }
         }
     }
@@ -137,7 +156,7 @@ impl<T> NameTable<T> {
     }
 
     pub fn position(&self, name: &str) -> Option<NonZeroUsize> {
-        let mut hasher = DefaultHasher::new();
+        let mut hasher = NameHasher::default();
         hasher.write(name.as_bytes());
         let hash = hasher.finish();
 
@@ -145,13 +164,7 @@ impl<T> NameTable<T> {
     }
 
     pub fn position_by_hash(&self, hash: u64) -> Option<NonZeroUsize> {
-        // SAFETY: This is safe because the "real" index always has the value of 1 added to it
-        unsafe {
-            self.hashes
-                .iter()
-                .position(|item| *item == hash)
-                .map(|index| NonZeroUsize::new_unchecked(index + 1))
-        }
+        self.positions.get(&hash).copied()
     }
 
     pub fn contains(&self, name: &str) -> bool {
@@ -162,11 +175,11 @@ impl<T> NameTable<T> {
         self.position_by_hash(hash).is_some()
     }
 
-    pub fn entries(&self) -> Iter<NameTableEntry<T>> {
+    pub fn entries(&self) -> Iter<'_, NameTableEntry<T>> {
         self.entries.iter()
     }
 
-    pub fn entries_mut(&mut self) -> IterMut<NameTableEntry<T>> {
+    pub fn entries_mut(&mut self) -> IterMut<'_, NameTableEntry<T>> {
         self.entries.iter_mut()
     }
 
@@ -174,3 +187,9 @@ impl<T> NameTable<T> {
         self.entries.drain(..).collect()
     }
 }
+
+impl<T> Default for NameTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,61 @@
+use std::num::NonZeroUsize;
+
+use kerbalobjects::ksm::Instr as KSMInstr;
+use kerbalobjects::Opcode;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn pad_functions_to_alignment() {
+    let config = CLIConfig::builder("./tests/align.ksm")
+        .with_align_functions(NonZeroUsize::new(4).unwrap())
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", Some("helper")));
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None));
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with aligned functions");
+        }
+    };
+
+    let offsets = driver.function_offsets();
+
+    assert_eq!(offsets["_start"], 4);
+    assert_eq!(offsets["helper"], 8);
+
+    let opcodes: Vec<Opcode> = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions())
+        .map(|instr| match instr {
+            KSMInstr::ZeroOp(opcode) => *opcode,
+            KSMInstr::OneOp(opcode, _) => *opcode,
+            KSMInstr::TwoOp(opcode, _, _) => *opcode,
+        })
+        .collect();
+
+    assert_eq!(
+        opcodes,
+        vec![
+            Opcode::Lbrt,
+            Opcode::Nop,
+            Opcode::Nop,
+            Opcode::Nop,
+            Opcode::Call,
+            Opcode::Push,
+            Opcode::Eop,
+            Opcode::Nop,
+            Opcode::Push,
+            Opcode::Ret,
+        ]
+    );
+}
@@ -0,0 +1,197 @@
+use std::io::Write;
+
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::archive::{Archive, ArchiveParseError, ArchiveWriteError};
+use klinker::driver::Driver;
+
+mod common;
+
+use common::{config_builder, write_and_parse, write_ko};
+
+#[test]
+fn link_with_archive() {
+    write_archive();
+
+    let config = config_builder("./tests/archive.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko());
+    driver.add_archive("./tests/archive/lib.kar");
+
+    // If the unused member were pulled in, its _start would be a duplicate definition
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link archive");
+    }
+}
+
+#[test]
+fn malformed_member_count() {
+    // Claims u32::MAX members, but ends right after the header
+    let bytes = [b'K', b'A', b'R', 1, 0xff, 0xff, 0xff, 0xff];
+
+    match Archive::parse(&bytes) {
+        Err(ArchiveParseError::UnexpectedEndError(position)) => assert_eq!(position, 8),
+        Err(e) => panic!("Expected an unexpected end error, found: {}", e),
+        Ok(_) => panic!("Parsing an archive with a bad member count should fail"),
+    }
+}
+
+#[test]
+fn reject_long_member_name() {
+    let mut archive = Archive::new();
+
+    archive.add("a".repeat(u16::MAX as usize + 1), Vec::new());
+
+    let mut file_buffer = Vec::new();
+
+    match archive.write(&mut file_buffer) {
+        Err(ArchiveWriteError::MemberNameTooLongError(name)) => {
+            assert_eq!(name.len(), u16::MAX as usize + 1)
+        }
+        Err(e) => panic!("Expected a member name too long error, found: {}", e),
+        Ok(_) => panic!("Writing a name longer than u16::MAX should fail"),
+    }
+
+    // Nothing is written unless the whole archive can be
+    assert!(file_buffer.is_empty());
+}
+
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let helper_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Extern,
+        SymType::Func,
+        data_section.section_index(),
+    );
+    let helper_symbol_index = symtab.add(helper_symbol);
+
+    // _start:
+    //      call helper, #
+    //      push 0
+    //      eop
+    let call_instr = start.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    reld_section.add(ReldEntry::new(
+        start.section_index(),
+        call_instr,
+        OperandIndex::One,
+        helper_symbol_index,
+    ));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file containing a single global function that just returns
+fn single_function_ko(func_name: &str, source_file_name: &str) -> Vec<u8> {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    func.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(source_file_name),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko(ko)
+}
+
+fn write_archive() {
+    let mut archive = Archive::new();
+
+    archive.add("helper.ko", single_function_ko("helper", "helper.kasm"));
+    archive.add("unused.ko", single_function_ko("_start", "unused.kasm"));
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    archive
+        .write(&mut file_buffer)
+        .expect("Archive could not be written");
+
+    let mut file = std::fs::File::create("./tests/archive/lib.kar")
+        .expect("Output file could not be created: lib.kar");
+
+    file.write_all(file_buffer.as_slice())
+        .expect("File lib.kar could not be written to.");
+}
@@ -0,0 +1,79 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::{driver::Driver, CLIConfig};
+
+#[test]
+fn reject_opcode_arity_mismatch() {
+    let config = CLIConfig::builder("./tests/arity.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), start_ko());
+
+    match driver.link() {
+        Err(LinkError::FuncContextError(
+            ctx,
+            ProcessingError::OpcodeArityMismatchError(instr_index, opcode, got, expected),
+        )) => {
+            assert_eq!(ctx.func_name, "_start");
+            assert_eq!(instr_index, 1);
+            assert_eq!(opcode, Opcode::Push);
+            assert_eq!(got, 0);
+            assert_eq!(expected, 1);
+        }
+        Err(e) => panic!("Expected an opcode arity error, found: {}", e),
+        Ok(_) => panic!("A push without an operand should not link"),
+    }
+}
+
+/// Creates a KO file whose _start has a push with no operand. It is never written and parsed
+/// again, because parsing reads as many operands as the opcode takes.
+fn start_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Push));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("start.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    ko.validate()
+        .expect("Could not update KO headers properly")
+        .into()
+}
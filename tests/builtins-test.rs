@@ -0,0 +1,43 @@
+use klinker::driver::errors::LinkWarning;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn warn_on_builtin_names() {
+    let warnings = link(true);
+
+    // stage is never called, so it is removed and can't shadow anything
+    assert_eq!(warnings.len(), 1);
+
+    match &warnings[0] {
+        LinkWarning::BuiltinShadowWarning(func_name, file_name) => {
+            assert_eq!(func_name, "Print");
+            assert_eq!(file_name, "print.ko");
+        }
+        warning => panic!("Expected a built-in shadow warning, found: {}", warning),
+    }
+
+    assert!(link(false).is_empty());
+}
+
+fn link(warn_builtins: bool) -> Vec<LinkWarning> {
+    let config = CLIConfig::builder("./tests/builtins.ksm")
+        .with_warn_builtins(warn_builtins)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", Some("Print")));
+    driver.add_file(String::from("print.ko"), func_ko("Print", None));
+    driver.add_file(String::from("stage.ko"), func_ko("stage", None));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link");
+    }
+
+    driver.warnings().cloned().collect()
+}
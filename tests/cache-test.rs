@@ -0,0 +1,130 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::cache::ObjectCache;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_ko_file};
+
+const INPUT_PATH: &str = "./tests/cache/main.ko";
+const INVALIDATE_PATH: &str = "./tests/cache/invalidate.ko";
+
+#[test]
+fn reuse_and_invalidate_cache() {
+    let cache = ObjectCache::new();
+
+    write_start_ko(INPUT_PATH, 1);
+
+    let first = link(Driver::with_cache(config(), cache.clone()), INPUT_PATH);
+
+    assert_eq!(cache.len(), 1);
+
+    // Nothing changed, so this comes from the cache
+    let cached = link(Driver::with_cache(config(), cache.clone()), INPUT_PATH);
+
+    assert_eq!(first, cached);
+
+    // Touching the file has to invalidate its entry
+    write_start_ko(INPUT_PATH, 2);
+
+    let changed = link(Driver::with_cache(config(), cache.clone()), INPUT_PATH);
+    let uncached = link(Driver::new(config()), INPUT_PATH);
+
+    assert_ne!(first, changed);
+    assert_eq!(changed, uncached);
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn invalidate_changed_input() {
+    let cache = ObjectCache::new();
+
+    write_start_ko(INVALIDATE_PATH, 3);
+
+    let first = link(Driver::with_cache(config(), cache.clone()), INVALIDATE_PATH);
+    link(Driver::with_cache(config(), cache.clone()), INVALIDATE_PATH);
+
+    assert_eq!(cache.hits(), 1);
+
+    // The file is reported as changed, so it is processed again
+    assert!(cache.invalidate(INVALIDATE_PATH));
+    assert!(!cache.invalidate(INVALIDATE_PATH));
+    assert!(cache.is_empty());
+
+    let relinked = link(Driver::with_cache(config(), cache.clone()), INVALIDATE_PATH);
+
+    assert_eq!(cache.hits(), 1);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(first, relinked);
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/cache.ksm").build()
+}
+
+fn link(mut driver: Driver, path: &str) -> Vec<u8> {
+    driver.add(path);
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link cached input");
+        }
+    };
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    ksm_file.write(&mut file_buffer);
+
+    file_buffer
+}
+
+/// Writes a KO file whose _start pushes the numbers from 1 to count
+fn write_start_ko(path: &str, count: i16) {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    for value in 1..=count {
+        let value_index = data_section.add(KOSValue::Int16(value));
+        start.add(Instr::OneOp(Opcode::Push, value_index));
+    }
+
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, path);
+}
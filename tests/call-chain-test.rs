@@ -0,0 +1,116 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+// Long enough that following each call with a recursive function call overflows the stack
+const CHAIN_LENGTH: usize = 4000;
+
+#[test]
+fn link_long_call_chain() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("chain.ko"), chain_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link call chain");
+    }
+
+    let function_offsets = driver.function_offsets();
+
+    // Every function in the chain is reachable from _start, but nothing calls unused
+    assert_eq!(function_offsets.len(), CHAIN_LENGTH + 1);
+    assert!(function_offsets.contains_key(&format!("link{}", CHAIN_LENGTH - 1)));
+    assert!(!function_offsets.contains_key("unused"));
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/call-chain.ksm").build()
+}
+
+/// Creates a KO file where _start calls link0, each link{i} calls link{i + 1}, and the last one
+/// returns. There is also a function that is never called.
+fn chain_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let call = Instr::TwoOp(Opcode::Call, DataIdx::PLACEHOLDER, null_value_index);
+
+    let mut names = vec![String::from("_start")];
+    names.extend((0..CHAIN_LENGTH).map(|i| format!("link{}", i)));
+    names.push(String::from("unused"));
+
+    let mut funcs = Vec::with_capacity(names.len());
+    let mut symbol_indexes = Vec::with_capacity(names.len());
+
+    for name in names.iter() {
+        let func = ko.new_func_section(name);
+
+        symbol_indexes.push(symtab.add(KOSymbol::new(
+            symstrtab.add(name),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Global,
+            SymType::Func,
+            func.section_index(),
+        )));
+
+        funcs.push(func);
+    }
+
+    for (index, func) in funcs.iter_mut().enumerate() {
+        // Every function but the last in the chain calls the next one
+        if index < CHAIN_LENGTH {
+            let call_instr = func.add(call);
+
+            reld_section.add(ReldEntry::new(
+                func.section_index(),
+                call_instr,
+                OperandIndex::One,
+                symbol_indexes[index + 1],
+            ));
+        }
+
+        func.add(Instr::OneOp(Opcode::Ret, zero_index));
+    }
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add("chain.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    ));
+
+    ko.add_data_section(data_section);
+
+    for func in funcs {
+        ko.add_func_section(func);
+    }
+
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,197 @@
+use std::path::PathBuf;
+
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::KSMFile;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn entry_point_comment() {
+    let ksm_file = link(config()).expect("Failed to link");
+
+    assert!(has_comment(&ksm_file, "main comment"));
+    assert!(!has_comment(&ksm_file, "lib comment"));
+}
+
+#[test]
+fn comment_from_file() {
+    let config = CLIConfig {
+        comment_from: Some(PathBuf::from("./build/lib.ko")),
+        ..config()
+    };
+
+    let ksm_file = link(config).expect("Failed to link");
+
+    assert!(has_comment(&ksm_file, "lib comment"));
+    assert!(!has_comment(&ksm_file, "main comment"));
+}
+
+#[test]
+fn comment_override() {
+    let config = CLIConfig {
+        comment: Some(String::from("version 1.2")),
+        ..config()
+    };
+
+    let ksm_file = link(config).expect("Failed to link");
+
+    assert!(has_comment(&ksm_file, "version 1.2"));
+    assert!(!has_comment(&ksm_file, "main comment"));
+}
+
+#[test]
+fn comment_from_missing_file() {
+    let config = CLIConfig {
+        comment_from: Some(PathBuf::from("other.ko")),
+        ..config()
+    };
+
+    match link(config) {
+        Err(LinkError::CommentSourceNotFoundError(path)) => assert_eq!(path, "other.ko"),
+        Err(e) => panic!("Expected a comment source error, found: {}", e),
+        Ok(_) => panic!("Taking the comment from a file that isn't an input should fail"),
+    }
+}
+
+#[test]
+fn every_comment_string() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(
+        String::from("main.ko"),
+        commented_ko(
+            "_start",
+            &["compiled by kOS 1.4", "", "assembled by kasm 2.0"],
+        ),
+    );
+
+    let ksm_file = driver.link().expect("Failed to link");
+
+    assert!(has_comment(
+        &ksm_file,
+        "compiled by kOS 1.4\nassembled by kasm 2.0"
+    ));
+}
+
+#[test]
+fn shared_object_comment() {
+    let config = CLIConfig::builder("./tests/comment.ksm")
+        .with_shared(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(
+        String::from("lib.ko"),
+        commented_ko("helper", &["lib comment"]),
+    );
+    driver.add_file(
+        String::from("init.ko"),
+        commented_ko("_init", &["init comment"]),
+    );
+
+    let ksm_file = driver.link().expect("Failed to link a shared object");
+
+    assert!(has_comment(&ksm_file, "init comment"));
+    assert!(!has_comment(&ksm_file, "lib comment"));
+
+    // Without a comment on _init's file, the first one found is used
+    let config = CLIConfig::builder("./tests/comment.ksm")
+        .with_shared(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("init.ko"), commented_ko("_init", &[]));
+    driver.add_file(
+        String::from("lib.ko"),
+        commented_ko("helper", &["lib comment"]),
+    );
+
+    let ksm_file = driver.link().expect("Failed to link a shared object");
+
+    assert!(has_comment(&ksm_file, "lib comment"));
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/comment.ksm").build()
+}
+
+fn link(config: CLIConfig) -> Result<KSMFile, LinkError> {
+    let mut driver = Driver::new(config);
+
+    driver.add_file(
+        String::from("main.ko"),
+        commented_ko("_start", &["main comment"]),
+    );
+    driver.add_file(
+        String::from("lib.ko"),
+        commented_ko("helper", &["lib comment"]),
+    );
+
+    driver.link()
+}
+
+fn has_comment(ksm_file: &KSMFile, comment: &str) -> bool {
+    ksm_file
+        .arg_section
+        .find(&KOSValue::String(String::from(comment)))
+        .is_some()
+}
+
+/// Creates a KO file with comments and a single global function that does nothing
+fn commented_ko(func_name: &str, comments: &[&str]) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut comment_section = ko.new_strtab(".comment");
+
+    for comment in comments {
+        comment_section.add(*comment);
+    }
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    func.add(Instr::OneOp(Opcode::Push, zero_index));
+    func.add(Instr::ZeroOp(Opcode::Eop));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_str_tab(comment_section);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
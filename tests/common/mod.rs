@@ -0,0 +1,205 @@
+// Not every test uses every helper in here
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{KOSymbol, OperandIndex, ReldEntry, SymBind, SymType};
+use kerbalobjects::ko::{self, KOFile, SectionIdx};
+use kerbalobjects::ksm::{Instr, KSMFile};
+use kerbalobjects::{BufferIterator, KOSValue, Opcode};
+use klinker::builder::CLIConfigBuilder;
+use klinker::driver::log::LogLevel;
+use klinker::CLIConfig;
+
+/// Starts a config that links to the given output path and logs everything it can
+pub fn config_builder(output_path: &str) -> CLIConfigBuilder {
+    CLIConfig::builder(output_path).with_verbosity(LogLevel::MAX)
+}
+
+/// Updates the headers of a KO file and writes it out
+pub fn write_ko(ko: KOFile) -> Vec<u8> {
+    let mut file_buffer = Vec::with_capacity(2048);
+
+    let ko = ko.validate().expect("Could not update KO headers properly");
+    ko.write(&mut file_buffer);
+
+    file_buffer
+}
+
+/// Writes a KO file to the given path, for tests that link from files on disk
+pub fn write_ko_file(ko: KOFile, path: impl AsRef<Path>) {
+    std::fs::write(path, write_ko(ko)).expect("Output file could not be written to");
+}
+
+/// Writes a KO file and reads it back, the same way the linker would get it from a file
+pub fn write_and_parse(ko: KOFile) -> KOFile {
+    let file_buffer = write_ko(ko);
+
+    let mut buffer_iter = BufferIterator::new(&file_buffer);
+
+    KOFile::parse(&mut buffer_iter).expect("Error reading KO file")
+}
+
+/// Creates a KO file with a single global function in it, which calls callee if it is given and
+/// then pushes 0. _start ends with eop, and every other function returns.
+pub fn func_ko(func_name: &str, callee: Option<&str>) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    if let Some(callee) = callee {
+        let callee_symbol = KOSymbol::new(
+            symstrtab.add(callee),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let callee_symbol_index = symtab.add(callee_symbol);
+
+        let call_instr = func.add(ko::Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            func.section_index(),
+            call_instr,
+            OperandIndex::One,
+            callee_symbol_index,
+        ));
+    }
+
+    func.add(ko::Instr::OneOp(Opcode::Push, zero_index));
+
+    if func_name == "_start" {
+        func.add(ko::Instr::ZeroOp(Opcode::Eop));
+    } else {
+        func.add(ko::Instr::OneOp(Opcode::Ret, zero_index));
+    }
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// A linked KSM file read back from its bytes, with every instruction in the order kOS loads them
+pub struct LinkedProgram {
+    instructions: Vec<Instr>,
+    labels: Vec<usize>,
+}
+
+impl LinkedProgram {
+    /// Parses the bytes written for a KSM file, and finds every jump target label in its code
+    pub fn parse(file_buffer: &[u8]) -> Self {
+        let mut buffer_iter = BufferIterator::new(file_buffer);
+
+        let ksm_file = KSMFile::parse_gzipped(&mut buffer_iter).expect("Error reading KSM file");
+
+        let instructions: Vec<Instr> = ksm_file
+            .code_sections()
+            .flat_map(|code_section| code_section.instructions().copied())
+            .collect();
+
+        let mut labels = Vec::new();
+
+        for instr in instructions.iter() {
+            let operands = match *instr {
+                // Label resets aren't jumps, they are where the labels come from
+                Instr::OneOp(Opcode::Lbrt, _) | Instr::ZeroOp(_) => Vec::new(),
+                Instr::OneOp(_, op) => vec![op],
+                Instr::TwoOp(_, op1, op2) => vec![op1, op2],
+            };
+
+            for op in operands {
+                if let Some(KOSValue::String(value)) = ksm_file.arg_section.get(op) {
+                    if let Some(label) = value.strip_prefix('@') {
+                        labels.push(label.parse().expect("Label is not a number"));
+                    }
+                }
+            }
+        }
+
+        LinkedProgram {
+            instructions,
+            labels,
+        }
+    }
+
+    /// The instruction index that every label in the code points to
+    pub fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    /// Checks that an instruction index is the first instruction of a function, which comes right
+    /// after the label reset at the start of the code or after the end of another function
+    pub fn starts_function(&self, index: usize) -> bool {
+        if index == 0 || index >= self.instructions.len() {
+            return false;
+        }
+
+        matches!(
+            self.instructions[index - 1],
+            Instr::OneOp(Opcode::Lbrt, _)
+                | Instr::OneOp(Opcode::Ret, _)
+                | Instr::ZeroOp(Opcode::Eop)
+        )
+    }
+
+    /// Checks that every label points to the start of a function, and that every global function
+    /// offset the driver recorded does too
+    pub fn assert_labels(&self, function_offsets: impl IntoIterator<Item = usize>) {
+        let offsets: HashSet<usize> = function_offsets.into_iter().collect();
+
+        for offset in offsets.iter() {
+            assert!(
+                self.starts_function(*offset),
+                "Function offset {} is not the start of a function",
+                offset
+            );
+        }
+
+        for label in self.labels.iter() {
+            assert!(
+                self.starts_function(*label),
+                "Label @{:0>4} does not point to the start of a function",
+                label
+            );
+        }
+    }
+}
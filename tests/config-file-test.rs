@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+
+use klinker::config_file::ConfigFile;
+use klinker::driver::errors::LinkError;
+use klinker::parse_args;
+
+#[test]
+fn config_file_sets_defaults() {
+    let config = parse_args([
+        "kld",
+        "main.ko",
+        "-o",
+        "main.ksm",
+        "--config",
+        "./tests/config/kld.toml",
+    ])
+    .expect("Failed to read the config file");
+
+    assert_eq!(config.entry_point, "main");
+    assert!(!config.shared);
+
+    // Search paths are relative to the config file
+    assert_eq!(
+        config.search_paths,
+        vec![
+            PathBuf::from("./tests/config/lib"),
+            PathBuf::from("./tests/config/vendor/lib")
+        ]
+    );
+}
+
+#[test]
+fn command_line_overrides_config_file() {
+    let config = parse_args([
+        "kld",
+        "main.ko",
+        "-o",
+        "main.ksm",
+        "--config",
+        "./tests/config/kld.toml",
+        "--entry-point=start",
+        "-L",
+        "first",
+    ])
+    .expect("Failed to read the config file");
+
+    assert_eq!(config.entry_point, "start");
+
+    // The command line's search paths are searched before the config file's
+    assert_eq!(
+        config.search_paths,
+        vec![
+            PathBuf::from("first"),
+            PathBuf::from("./tests/config/lib"),
+            PathBuf::from("./tests/config/vendor/lib")
+        ]
+    );
+}
+
+#[test]
+fn missing_config_file() {
+    let result = parse_args([
+        "kld",
+        "main.ko",
+        "-o",
+        "main.ksm",
+        "--config",
+        "./tests/config/missing.toml",
+    ]);
+
+    match result {
+        Err(LinkError::IOError(file_name, _)) => {
+            assert_eq!(file_name, "./tests/config/missing.toml")
+        }
+        Err(e) => panic!("Expected an I/O error, found: {}", e),
+        Ok(_) => panic!("A missing config file should fail"),
+    }
+}
+
+#[test]
+fn reject_invalid_config_file() {
+    let parse_error = |contents: &str| {
+        ConfigFile::parse(contents).expect_err("Invalid config file should not parse")
+    };
+
+    assert_eq!(parse_error("shared = true\nshraed = true").0, 2);
+    assert_eq!(parse_error("shared = \"yes\"").0, 1);
+    assert_eq!(parse_error("entry_point = \"main").0, 1);
+    assert_eq!(parse_error("\n\n[link]").0, 3);
+    assert_eq!(
+        parse_error("search_paths = [\n    \"lib\",\n    3,\n]").0,
+        3
+    );
+}
+
+#[test]
+fn parse_multi_line_values() {
+    let contents = "entry_point = 'main'\nsearch_paths = [\n    \"lib\", # vendored code is last\n    'C:\\vendor',\n]\n";
+
+    let config_file = ConfigFile::parse(contents).expect("Failed to parse the config file");
+
+    assert_eq!(config_file.entry_point.as_deref(), Some("main"));
+    assert_eq!(config_file.shared, None);
+    assert_eq!(
+        config_file.search_paths,
+        vec![PathBuf::from("lib"), PathBuf::from("C:\\vendor")]
+    );
+}
@@ -0,0 +1,63 @@
+use kerbalobjects::ksm::sections::DebugRange;
+use klinker::driver::debug::{debug_entry, debug_map, SourceMapEntry};
+
+#[test]
+fn debug_ranges_are_ordered_and_non_empty() {
+    let entry = debug_entry(
+        3,
+        [
+            DebugRange::new(20, 24),
+            // Empty and backwards ranges are dropped
+            DebugRange::new(8, 8),
+            DebugRange::new(16, 12),
+            // Overlapping and touching ranges are merged
+            DebugRange::new(2, 6),
+            DebugRange::new(4, 10),
+            DebugRange::new(24, 30),
+        ],
+    )
+    .expect("Entry should have ranges left");
+
+    let ranges: Vec<DebugRange> = entry.ranges().copied().collect();
+
+    assert_eq!(
+        ranges,
+        vec![DebugRange::new(2, 10), DebugRange::new(20, 30)]
+    );
+
+    for range in ranges.iter() {
+        assert!(range.start < range.end);
+    }
+
+    for pair in ranges.windows(2) {
+        assert!(pair[0].end < pair[1].start);
+    }
+}
+
+#[test]
+fn drop_entry_without_ranges() {
+    assert!(debug_entry(1, [DebugRange::new(4, 4), DebugRange::new(6, 2)]).is_none());
+}
+
+#[test]
+fn debug_map_lists_ranges() {
+    let source_map = [
+        SourceMapEntry {
+            start: 1,
+            end: 3,
+            func_name: String::from("_start"),
+            source_file_name: String::from("main.kasm"),
+        },
+        SourceMapEntry {
+            start: 12,
+            end: 20,
+            func_name: String::from("helper"),
+            source_file_name: String::from("lib.kasm"),
+        },
+    ];
+
+    assert_eq!(
+        debug_map(&source_map),
+        "@0001\t@0003\t_start\tmain.kasm\n@0012\t@0020\thelper\tlib.kasm\n"
+    );
+}
@@ -0,0 +1,182 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn link_direct_symrefs() {
+    let relocated = link(main_ko(true), true).expect("Failed to link relocated references");
+    let direct = link(main_ko(false), true).expect("Failed to link direct references");
+
+    let mut relocated_buffer = Vec::new();
+    let mut direct_buffer = Vec::new();
+
+    relocated.write(&mut relocated_buffer);
+    direct.write(&mut direct_buffer);
+
+    // Both encodings of the same reference should produce the same program
+    assert_eq!(relocated_buffer, direct_buffer);
+}
+
+#[test]
+fn reject_direct_symrefs_by_default() {
+    match link(main_ko(false), false) {
+        Err(LinkError::FuncContextError(_, ProcessingError::InvalidDataIndexError(..))) => {}
+        Err(e) => panic!("Expected an invalid data index error, found: {}", e),
+        Ok(_) => panic!("Direct symbol references should need --resolve-direct-symrefs"),
+    }
+}
+
+fn link(
+    main: KOFile,
+    resolve_direct_symrefs: bool,
+) -> Result<kerbalobjects::ksm::KSMFile, LinkError> {
+    let config = CLIConfig::builder("./tests/direct.ksm")
+        .with_resolve_direct_symrefs(resolve_direct_symrefs)
+        .with_verbosity(LogLevel::MAX)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main);
+    driver.add_file(String::from("helper.ko"), helper_ko());
+
+    driver.link()
+}
+
+/// Creates a KO file whose _start calls helper. The call either has a relocation entry, or has the
+/// symbol table index of helper written directly into its operand.
+fn main_ko(relocated: bool) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+    symtab.add(file_symbol);
+
+    // Padding so that the helper's symbol index can't be mistaken for a data index
+    let padding_symbol = KOSymbol::new(
+        symstrtab.add("padding"),
+        null_value_index,
+        0,
+        SymBind::Local,
+        SymType::NoType,
+        data_section.section_index(),
+    );
+    symtab.add(padding_symbol);
+
+    let helper_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Extern,
+        SymType::Func,
+        data_section.section_index(),
+    );
+    let helper_symbol_index = symtab.add(helper_symbol);
+
+    let call_operand = if relocated {
+        DataIdx::PLACEHOLDER
+    } else {
+        DataIdx::from(u32::from(helper_symbol_index))
+    };
+
+    let call_instr = start.add(Instr::TwoOp(Opcode::Call, call_operand, null_value_index));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    if relocated {
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            helper_symbol_index,
+        ));
+    }
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a single global function helper that just returns
+fn helper_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section("helper");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    func.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("helper.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
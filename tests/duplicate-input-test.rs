@@ -0,0 +1,147 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, LinkWarning, ProcessingError};
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_ko_file};
+
+// Each test writes its own input, so that one test never reads a file another is writing
+const SKIP_INPUT_PATH: &str = "./tests/duplicate-skip.ko";
+const ALLOW_INPUT_PATH: &str = "./tests/duplicate-allow.ko";
+const CLEAR_INPUT_PATH: &str = "./tests/duplicate-clear.ko";
+
+#[test]
+fn skip_duplicate_input() {
+    write_start_ko(SKIP_INPUT_PATH);
+
+    let mut driver = Driver::new(config(false));
+
+    driver.add(SKIP_INPUT_PATH);
+    // The same file through a different path
+    driver.add("./tests/../tests/duplicate-skip.ko");
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Linking the same input twice should only link it once");
+    }
+
+    let warnings: Vec<&LinkWarning> = driver.warnings().collect();
+
+    assert!(matches!(
+        warnings.as_slice(),
+        [LinkWarning::DuplicateInputWarning(name)] if name == "./tests/../tests/duplicate-skip.ko"
+    ));
+}
+
+#[test]
+fn allow_duplicate_inputs() {
+    write_start_ko(ALLOW_INPUT_PATH);
+
+    let mut driver = Driver::new(config(true));
+
+    driver.add(ALLOW_INPUT_PATH);
+    driver.add(ALLOW_INPUT_PATH);
+
+    match driver.link() {
+        Err(LinkError::FileContextError(_, ProcessingError::DuplicateSymbolError(name, ..))) => {
+            assert_eq!(name, "_start");
+        }
+        Err(e) => panic!("Expected a duplicate symbol error, found: {}", e),
+        Ok(_) => panic!("Linking an allowed duplicate input should define _start twice"),
+    }
+}
+
+#[test]
+fn clear_forgets_inputs() {
+    write_start_ko(CLEAR_INPUT_PATH);
+
+    let mut driver = Driver::new(config(false));
+
+    driver.add(CLEAR_INPUT_PATH);
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link before clearing");
+    }
+
+    assert!(driver.summary().is_some());
+
+    // An input that is still being read is dropped along with everything from the last link
+    driver.add("./tests/does-not-exist.ko");
+    driver.clear();
+
+    assert!(driver.summary().is_none());
+    assert!(driver.function_offsets().is_empty());
+
+    match driver.link() {
+        Err(LinkError::NoInputFiles) => {}
+        Err(e) => panic!("Expected no input files, found: {}", e),
+        Ok(_) => panic!("A cleared driver should have no inputs"),
+    }
+
+    // The same path isn't a duplicate of an input from before clearing
+    driver.add(CLEAR_INPUT_PATH);
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link after clearing");
+    }
+
+    assert_eq!(driver.warnings().count(), 0);
+}
+
+fn config(allow_duplicate_inputs: bool) -> CLIConfig {
+    config_builder("./tests/duplicate.ksm")
+        .with_allow_duplicate_inputs(allow_duplicate_inputs)
+        .with_verbosity(LogLevel::MAX)
+        .build()
+}
+
+/// Writes a KO file to the given path with a _start that does nothing
+fn write_start_ko(path: &str) {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("duplicate.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, path);
+}
@@ -0,0 +1,114 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::sections::CodeType;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn jump_into_entry_point() {
+    let mut driver = Driver::new(config(1));
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with an entry offset");
+        }
+    };
+
+    let main_section = ksm_file
+        .code_sections()
+        .find(|section| section.section_type == CodeType::Main)
+        .unwrap();
+    let instructions: Vec<_> = main_section.instructions().collect();
+
+    // lbrt, jmp, then the three instructions of _start
+    assert_eq!(instructions.len(), 5);
+
+    match instructions[1] {
+        kerbalobjects::ksm::Instr::OneOp(Opcode::Jmp, label_index) => {
+            // _start begins at @0002, so one instruction in is @0003
+            assert_eq!(
+                ksm_file.arg_section.get(*label_index),
+                Some(&KOSValue::String(String::from("@0003")))
+            );
+        }
+        instr => panic!("Expected a jump into the entry point, found: {:?}", instr),
+    }
+}
+
+#[test]
+fn reject_entry_offset_past_end() {
+    let mut driver = Driver::new(config(3));
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    match driver.link() {
+        Err(LinkError::InvalidEntryOffsetError(3, 3)) => {}
+        Err(e) => panic!("Expected an invalid entry offset error, found: {}", e),
+        Ok(_) => panic!("An entry offset past the end of the entry point should fail"),
+    }
+}
+
+fn config(entry_offset: usize) -> CLIConfig {
+    config_builder("./tests/entry-offset.ksm")
+        .with_entry_offset(entry_offset)
+        .with_verbosity(LogLevel::MAX)
+        .build()
+}
+
+/// Creates a KO file whose _start has three instructions
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    let one_index = data_section.add(KOSValue::Int16(1));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::OneOp(Opcode::Push, one_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
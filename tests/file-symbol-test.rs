@@ -0,0 +1,90 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::driver::reader::Reader;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn reject_duplicate_file_symbols() {
+    let config = CLIConfig::builder("./tests/file-symbol.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(
+        String::from("main.ko"),
+        main_ko(&["main.kasm", "other.kasm"]),
+    );
+
+    match driver.link() {
+        Err(LinkError::DuplicateFileSymbolError(file_name, first, second)) => {
+            assert_eq!(file_name, "main.ko");
+            assert_eq!(first, "main.kasm");
+            assert_eq!(second, "other.kasm");
+        }
+        Err(e) => panic!("Expected a duplicate file symbol error, found: {}", e),
+        Ok(_) => panic!("A file with two FILE symbols should not link"),
+    }
+}
+
+#[test]
+fn single_file_symbol() {
+    let source_file_name = Reader::source_file_name("main.ko", &main_ko(&["main.kasm"]))
+        .expect("Failed to read the file symbol");
+
+    assert_eq!(source_file_name, "main.kasm");
+}
+
+/// Creates a KO file with a _start function and a FILE symbol for each of the given names
+fn main_ko(source_file_names: &[&str]) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    for source_file_name in source_file_names {
+        let file_symbol = KOSymbol::new(
+            symstrtab.add(*source_file_name),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Global,
+            SymType::File,
+            SectionIdx::NULL,
+        );
+
+        symtab.add(file_symbol);
+    }
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,377 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::driver::reader::Reader;
+use klinker::tables::ObjectData;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn fold_identical_globals() {
+    let mut driver = Driver::new(config(true));
+
+    driver.add_file(String::from("main.ko"), main_ko(&["helper"]));
+    driver.add_file(String::from("liba.ko"), helper_ko("liba.kasm", 1));
+    driver.add_file(String::from("libb.ko"), helper_ko("libb.kasm", 1));
+
+    let folded = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to fold identical functions");
+        }
+    };
+
+    let helpers = driver
+        .summary()
+        .unwrap()
+        .retained_functions
+        .iter()
+        .filter(|func| func.name == "helper")
+        .count();
+
+    assert_eq!(helpers, 1);
+
+    // Folding should produce exactly what linking a single copy would
+    let mut driver = Driver::new(config(false));
+
+    driver.add_file(String::from("main.ko"), main_ko(&["helper"]));
+    driver.add_file(String::from("liba.ko"), helper_ko("liba.kasm", 1));
+
+    let single = driver.link().expect("Failed to link a single copy");
+
+    let mut folded_buffer = Vec::new();
+    let mut single_buffer = Vec::new();
+
+    folded.write(&mut folded_buffer);
+    single.write(&mut single_buffer);
+
+    assert_eq!(folded_buffer, single_buffer);
+}
+
+#[test]
+fn reject_different_globals() {
+    let mut driver = Driver::new(config(true));
+
+    driver.add_file(String::from("main.ko"), main_ko(&["helper"]));
+    driver.add_file(String::from("liba.ko"), helper_ko("liba.kasm", 1));
+    driver.add_file(String::from("libb.ko"), helper_ko("libb.kasm", 2));
+
+    match driver.link() {
+        Err(LinkError::FileContextError(_, ProcessingError::DuplicateSymbolError(name, ..))) => {
+            assert_eq!(name, "helper");
+        }
+        Err(e) => panic!("Expected a duplicate symbol error, found: {}", e),
+        Ok(_) => panic!("Functions with different bodies should not be folded"),
+    }
+}
+
+#[test]
+fn fold_identical_locals() {
+    let mut driver = Driver::new(config(true));
+
+    driver.add_file(String::from("main.ko"), main_ko(&["helpera", "helperb"]));
+    driver.add_file(
+        String::from("liba.ko"),
+        local_helper_ko("liba.kasm", "helpera"),
+    );
+    driver.add_file(
+        String::from("libb.ko"),
+        local_helper_ko("libb.kasm", "helperb"),
+    );
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to fold identical local functions");
+    }
+
+    let adds = driver
+        .summary()
+        .unwrap()
+        .retained_functions
+        .iter()
+        .filter(|func| func.name == "_add")
+        .count();
+
+    assert_eq!(adds, 1);
+}
+
+#[test]
+fn local_hashes_include_file() {
+    let liba = Reader::process_file(
+        String::from("liba.ko"),
+        local_helper_ko("liba.kasm", "helpera"),
+        false,
+        false,
+    )
+    .expect("Failed to process liba.ko");
+    let libb = Reader::process_file(
+        String::from("libb.ko"),
+        local_helper_ko("libb.kasm", "helperb"),
+        false,
+        false,
+    )
+    .expect("Failed to process libb.ko");
+
+    let liba_add = liba.local_hash("_add");
+    let libb_add = libb.local_hash("_add");
+
+    // Both files have a local _add, which should never be mistaken for the other
+    assert_ne!(liba_add, libb_add);
+    assert!(liba
+        .local_function_name_table
+        .get_by_hash(liba_add)
+        .is_some());
+    assert!(libb
+        .local_function_name_table
+        .get_by_hash(liba_add)
+        .is_none());
+    assert!(liba.local_symbol_table.get_by_hash(liba_add).is_some());
+
+    // Or for a global with the same name
+    assert!(liba.local_function_name_table.get("_add").is_none());
+}
+
+#[test]
+fn structural_hash_ignores_data_indices() {
+    let process = |file_name: &str, kofile: KOFile| {
+        Reader::process_file(file_name.to_owned(), kofile, false, false)
+            .unwrap_or_else(|e| panic!("Failed to process {}: {}", file_name, e))
+    };
+
+    let helper_hash = |object_data: &ObjectData| {
+        object_data
+            .function_table
+            .functions()
+            .next()
+            .unwrap()
+            .structural_hash()
+    };
+
+    // The value is at a different data index in each file, but it is the same value
+    let liba = process("liba.ko", helper_ko("liba.kasm", 1));
+    let libb = process("libb.ko", helper_ko("libb.kasm", 1));
+    let libc = process("libc.ko", helper_ko("libc.kasm", 2));
+
+    assert_eq!(helper_hash(&liba), helper_hash(&libb));
+    assert_ne!(helper_hash(&liba), helper_hash(&libc));
+
+    // Calls to a local function depend on which file it is in
+    let locala = process("locala.ko", local_helper_ko("locala.kasm", "helper"));
+    let localb = process("localb.ko", local_helper_ko("localb.kasm", "helper"));
+
+    assert_ne!(helper_hash(&locala), helper_hash(&localb));
+
+    let add_hash = |object_data: &ObjectData| {
+        object_data
+            .local_function_table
+            .functions()
+            .next()
+            .unwrap()
+            .structural_hash()
+    };
+
+    assert_eq!(add_hash(&locala), add_hash(&localb));
+}
+
+fn config(fold_identical: bool) -> CLIConfig {
+    config_builder("./tests/fold.ksm")
+        .with_fold_identical(fold_identical)
+        .build()
+}
+
+/// Creates a KO file whose _start calls each of the given global functions
+fn main_ko(helpers: &[&str]) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    for helper in helpers {
+        let helper_symbol = KOSymbol::new(
+            symstrtab.add(*helper),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let helper_symbol_index = symtab.add(helper_symbol);
+
+        let call_instr = start.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            helper_symbol_index,
+        ));
+    }
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a global function helper that returns the given value
+fn helper_ko(source_file_name: &str, value: i16) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section("helper");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    // Pads the data section so that the same value lands at a different index in each file
+    data_section.add(KOSValue::String(source_file_name.to_owned()));
+    let value_index = data_section.add(KOSValue::Int16(value));
+
+    func.add(Instr::OneOp(Opcode::Ret, value_index));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(source_file_name),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a global function that calls a local function _add, which is the same
+/// in every file
+fn local_helper_ko(source_file_name: &str, global_name: &str) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut global_func = ko.new_func_section(global_name);
+    let mut add_func = ko.new_func_section("_add");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let add_symbol = KOSymbol::new(
+        symstrtab.add("_add"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Local,
+        SymType::Func,
+        add_func.section_index(),
+    );
+    let add_symbol_index = symtab.add(add_symbol);
+
+    let call_instr = global_func.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    global_func.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    add_func.add(Instr::ZeroOp(Opcode::Add));
+    add_func.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    reld_section.add(ReldEntry::new(
+        global_func.section_index(),
+        call_instr,
+        OperandIndex::One,
+        add_symbol_index,
+    ));
+
+    let global_symbol = KOSymbol::new(
+        symstrtab.add(global_name),
+        DataIdx::PLACEHOLDER,
+        global_func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        global_func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(source_file_name),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(global_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(global_func);
+    ko.add_func_section(add_func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,135 @@
+use std::io::{Cursor, ErrorKind, Read, Write};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_ko};
+
+#[test]
+fn link_gzipped_input() {
+    let bytes = start_ko_bytes();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&bytes)
+        .expect("Could not compress KO file");
+    let gzipped = encoder.finish().expect("Could not compress KO file");
+
+    assert_eq!(link(bytes), link(gzipped));
+}
+
+#[test]
+fn link_from_reader() {
+    let bytes = start_ko_bytes();
+
+    let mut driver = Driver::new(config());
+
+    driver.add_reader(
+        String::from("main.ko"),
+        Box::new(Cursor::new(bytes.clone())),
+    );
+
+    let ksm_file = driver.link().expect("Failed to link input from a reader");
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    ksm_file.write(&mut file_buffer);
+
+    assert_eq!(file_buffer, link(bytes));
+
+    // An error while reading is reported for that input
+    let mut driver = Driver::new(config());
+
+    driver.add_reader(String::from("broken.ko"), Box::new(BrokenReader));
+
+    match driver.link() {
+        Err(LinkError::IOError(file_name, kind)) => {
+            assert_eq!(file_name, "broken.ko");
+            assert_eq!(kind, ErrorKind::BrokenPipe);
+        }
+        Err(e) => panic!("Expected an IO error, found: {}", e),
+        Ok(_) => panic!("Linking an input that can't be read should fail"),
+    }
+}
+
+struct BrokenReader;
+
+impl Read for BrokenReader {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        Err(ErrorKind::BrokenPipe.into())
+    }
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/gzip.ksm").build()
+}
+
+fn link(bytes: Vec<u8>) -> Vec<u8> {
+    let mut driver = Driver::new(config());
+
+    driver.add_bytes(String::from("main.ko"), bytes);
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link input");
+        }
+    };
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    ksm_file.write(&mut file_buffer);
+
+    file_buffer
+}
+
+/// Writes a KO file whose _start pushes a number into memory
+fn start_ko_bytes() -> Vec<u8> {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let value_index = data_section.add(KOSValue::Int16(42));
+    start.add(Instr::OneOp(Opcode::Push, value_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko(ko)
+}
@@ -0,0 +1,65 @@
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn hybrid_keeps_globals() {
+    let config = CLIConfig::builder("./tests/hybrid.ksm")
+        .with_hybrid(true)
+        .with_hidden_symbol("internal")
+        .with_validate(true)
+        .build();
+
+    let mut driver = link(config);
+
+    let retained: Vec<&str> = driver
+        .summary()
+        .unwrap()
+        .retained_functions
+        .iter()
+        .map(|func| func.name.as_str())
+        .collect();
+
+    // Nothing calls library, but it is exported, while internal is only kept if it is used
+    assert_eq!(retained, vec!["_start", "library"]);
+
+    let exports = driver.exports();
+
+    assert!(exports.contains(&String::from("library")));
+    assert!(!exports.contains(&String::from("internal")));
+
+    let offsets = driver.function_offsets();
+
+    // _start is two instructions long, right after the label reset
+    assert_eq!(offsets["_start"], 1);
+    assert_eq!(offsets["library"], 3);
+
+    // An executable only keeps what _start reaches
+    let config = CLIConfig::builder("./tests/not-hybrid.ksm").build();
+
+    driver = link(config);
+
+    assert!(driver
+        .summary()
+        .unwrap()
+        .retained_functions
+        .iter()
+        .all(|func| func.name == "_start"));
+}
+
+fn link(config: CLIConfig) -> Driver {
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", None));
+    driver.add_file(String::from("library.ko"), func_ko("library", None));
+    driver.add_file(String::from("internal.ko"), func_ko("internal", None));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link");
+    }
+
+    driver
+}
@@ -0,0 +1,40 @@
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn reject_init_entry_point() {
+    let config = CLIConfig::builder("./tests/init-entry.ksm")
+        .with_entry_point("_init")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("init.ko"), func_ko("_init", None));
+
+    match driver.link() {
+        Err(LinkError::InitEntryPointError) => {}
+        Err(e) => panic!("Expected an init entry point error, found: {}", e),
+        Ok(_) => panic!("_init should not be usable as the entry point"),
+    }
+}
+
+#[test]
+fn shared_init_is_not_entry_point() {
+    let config = CLIConfig::builder("./tests/init-entry-shared.ksm")
+        .with_shared(true)
+        .with_entry_point("_init")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("init.ko"), func_ko("_init", None));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("A shared object has no entry point, so _init should link");
+    }
+}
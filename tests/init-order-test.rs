@@ -0,0 +1,31 @@
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn init_runs_first() {
+    let config = CLIConfig::builder("./tests/init-order.ksm")
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    // _init is in the last input, and is the only thing that reaches helper
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None));
+    driver.add_file(String::from("start.ko"), func_ko("_start", None));
+    driver.add_file(String::from("init.ko"), func_ko("_init", Some("helper")));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link _init");
+    }
+
+    let offsets = driver.function_offsets();
+
+    // _init is 3 instructions long, and _start is 2, right after the label reset
+    assert_eq!(offsets["_init"], 1);
+    assert_eq!(offsets["_start"], 4);
+    assert_eq!(offsets["helper"], 6);
+}
@@ -0,0 +1,211 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::{self, KSMFile};
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn inline_leaf_function() {
+    let config = CLIConfig::builder("./tests/inline.ksm")
+        .with_inline_threshold(2)
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(
+        String::from("start.ko"),
+        func_ko("_start", Some("double"), 1),
+    );
+    driver.add_file(String::from("double.ko"), func_ko("double", None, 2));
+
+    let ksm_file = link(&mut driver);
+
+    assert_eq!(opcodes(&ksm_file, "_start", &driver), vec![Opcode::Push; 3]);
+    assert_eq!(count_calls(&ksm_file), 0);
+}
+
+#[test]
+fn keep_calls_over_threshold() {
+    let config = CLIConfig::builder("./tests/inline-threshold.ksm")
+        .with_inline_threshold(1)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(
+        String::from("start.ko"),
+        func_ko("_start", Some("double"), 1),
+    );
+    driver.add_file(String::from("double.ko"), func_ko("double", None, 2));
+
+    let ksm_file = link(&mut driver);
+
+    assert_eq!(count_calls(&ksm_file), 1);
+}
+
+#[test]
+fn keep_calls_to_non_leaf_functions() {
+    let config = CLIConfig::builder("./tests/inline-non-leaf.ksm")
+        .with_inline_threshold(10)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(
+        String::from("start.ko"),
+        func_ko("_start", Some("outer"), 1),
+    );
+    driver.add_file(String::from("outer.ko"), func_ko("outer", Some("inner"), 1));
+    driver.add_file(String::from("inner.ko"), func_ko("inner", None, 1));
+
+    let ksm_file = link(&mut driver);
+
+    // inner is a leaf and is inlined into outer, but outer calls something and is left alone
+    assert_eq!(count_calls(&ksm_file), 1);
+    assert_eq!(
+        opcodes(&ksm_file, "outer", &driver),
+        vec![Opcode::Push, Opcode::Push]
+    );
+}
+
+#[test]
+fn keep_calls_to_exports() {
+    let config = CLIConfig::builder("./tests/inline-shared.ksm")
+        .with_shared(true)
+        .with_inline_threshold(10)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("init.ko"), func_ko("_init", Some("double"), 1));
+    driver.add_file(String::from("double.ko"), func_ko("double", None, 2));
+
+    let ksm_file = link(&mut driver);
+
+    assert_eq!(count_calls(&ksm_file), 1);
+}
+
+fn link(driver: &mut Driver) -> KSMFile {
+    match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with inlining");
+        }
+    }
+}
+
+fn instructions(ksm_file: &KSMFile) -> Vec<&ksm::Instr> {
+    ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions())
+        .collect()
+}
+
+fn count_calls(ksm_file: &KSMFile) -> usize {
+    instructions(ksm_file)
+        .iter()
+        .filter(|instr| instr.opcode() == Opcode::Call)
+        .count()
+}
+
+/// The opcodes of a function in the output, up to its Eop or Ret
+fn opcodes(ksm_file: &KSMFile, func_name: &str, driver: &Driver) -> Vec<Opcode> {
+    let offset = driver.function_offsets()[func_name];
+
+    instructions(ksm_file)[offset..]
+        .iter()
+        .map(|instr| instr.opcode())
+        .take_while(|opcode| *opcode != Opcode::Eop && *opcode != Opcode::Ret)
+        .collect()
+}
+
+/// Creates a KO file with a single global function that calls another global function if one is
+/// given, and then pushes the given number of values
+fn func_ko(func_name: &str, callee: Option<&str>, pushes: usize) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    if let Some(callee) = callee {
+        let callee_symbol = KOSymbol::new(
+            symstrtab.add(callee),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let callee_symbol_index = symtab.add(callee_symbol);
+
+        let call_instr = func.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            func.section_index(),
+            call_instr,
+            OperandIndex::One,
+            callee_symbol_index,
+        ));
+    }
+
+    for _ in 0..pushes {
+        func.add(Instr::OneOp(Opcode::Push, zero_index));
+    }
+
+    if func_name == "_start" {
+        func.add(Instr::ZeroOp(Opcode::Eop));
+    } else {
+        func.add(Instr::OneOp(Opcode::Ret, zero_index));
+    }
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,109 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::inspect_object;
+
+mod common;
+
+use common::write_ko_file;
+
+const INPUT_PATH: &str = "./tests/inspect.ko";
+
+#[test]
+fn inspect_without_linking() {
+    write_ko();
+
+    let object_data = match inspect_object(INPUT_PATH) {
+        Ok(object_data) => object_data,
+        Err(e) => panic!("Failed to inspect object: {}", e),
+    };
+
+    assert_eq!(object_data.input_file_name, "inspect.ko");
+    assert_eq!(object_data.source_file_name, "inspect.kasm");
+
+    let functions: Vec<&String> = object_data
+        .function_name_table
+        .entries()
+        .map(|entry| entry.name())
+        .collect();
+    let local_functions: Vec<&String> = object_data
+        .local_function_name_table
+        .entries()
+        .map(|entry| entry.name())
+        .collect();
+
+    assert_eq!(functions, vec!["_start"]);
+    assert_eq!(local_functions, vec!["helper"]);
+    assert!(object_data.symbol_name_table.contains("version"));
+}
+
+#[test]
+fn inspect_missing_file() {
+    match inspect_object("./tests/does-not-exist.ko") {
+        Err(LinkError::IOError(..)) => {}
+        Err(e) => panic!("Expected an I/O error, found: {}", e),
+        Ok(_) => panic!("Inspecting a file that doesn't exist should fail"),
+    }
+}
+
+/// Writes a KO file with a global function, a local function, and a global value
+fn write_ko() {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut helper = ko.new_func_section("helper");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    let version_index = data_section.add(KOSValue::Int16(3));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+    helper.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    for (name, sym_bind, section_index) in [
+        ("_start", SymBind::Global, start.section_index()),
+        ("helper", SymBind::Local, helper.section_index()),
+    ] {
+        symtab.add(KOSymbol::new(
+            symstrtab.add(name),
+            DataIdx::PLACEHOLDER,
+            0,
+            sym_bind,
+            SymType::Func,
+            section_index,
+        ));
+    }
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add("version"),
+        version_index,
+        2,
+        SymBind::Global,
+        SymType::NoType,
+        data_section.section_index(),
+    ));
+    symtab.add(KOSymbol::new(
+        symstrtab.add("inspect.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    ));
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_func_section(helper);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, INPUT_PATH);
+}
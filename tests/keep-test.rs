@@ -0,0 +1,59 @@
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn keep_unreferenced_function() {
+    let config = CLIConfig::builder("./tests/keep.ksm")
+        .with_keep_symbol("handler")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", None));
+    driver.add_file(
+        String::from("handler.ko"),
+        func_ko("handler", Some("helper")),
+    );
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None));
+    driver.add_file(String::from("unused.ko"), func_ko("unused", None));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link with a kept function");
+    }
+
+    let summary = driver.summary().unwrap();
+
+    let retained: Vec<&str> = summary
+        .retained_functions
+        .iter()
+        .map(|func| func.name.as_str())
+        .collect();
+
+    // Everything the kept function calls is kept with it
+    assert!(retained.contains(&"handler"));
+    assert!(retained.contains(&"helper"));
+    assert!(!retained.contains(&"unused"));
+}
+
+#[test]
+fn reject_missing_keep() {
+    let config = CLIConfig::builder("./tests/keep-missing.ksm")
+        .with_keep_symbol("hanlder")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", None));
+    driver.add_file(String::from("handler.ko"), func_ko("handler", None));
+
+    match driver.link() {
+        Err(LinkError::MissingKeepSymbolError(name)) => assert_eq!(name, "hanlder"),
+        Err(e) => panic!("Expected a missing keep symbol error, found: {}", e),
+        Ok(_) => panic!("Keeping a function that doesn't exist should fail"),
+    }
+}
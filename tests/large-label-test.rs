@@ -0,0 +1,127 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn labels_past_four_digits() {
+    let config = CLIConfig::builder("./tests/large-label.ksm")
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko(10_000));
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link a program with more than 9999 instructions");
+        }
+    };
+
+    let helper_offset = driver.function_offsets()["helper"];
+
+    assert!(helper_offset > 9999);
+
+    let call_label = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions())
+        .find_map(|instr| match instr {
+            ksm::Instr::TwoOp(Opcode::Call, label, _) => ksm_file.arg_section.get(*label),
+            _ => None,
+        })
+        .expect("The call to helper is missing");
+
+    // The label grows past four digits instead of wrapping or being cut off
+    assert_eq!(call_label, &KOSValue::String(format!("@{}", helper_offset)));
+}
+
+/// Creates a KO file whose _start pads itself out with the given number of Nop instructions before
+/// calling helper, which comes after it
+fn main_ko(padding: usize) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut helper = ko.new_func_section("helper");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    for _ in 0..padding {
+        start.add(Instr::ZeroOp(Opcode::Nop));
+    }
+
+    let helper_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::Func,
+        helper.section_index(),
+    );
+    let helper_symbol_index = symtab.add(helper_symbol);
+
+    let call_instr = start.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+
+    reld_section.add(ReldEntry::new(
+        start.section_index(),
+        call_instr,
+        OperandIndex::One,
+        helper_symbol_index,
+    ));
+
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    helper.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_func_section(helper);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,63 @@
+use klinker::driver::errors::{LinkError, LinkWarning};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn layout_listed_functions_first() {
+    let config = CLIConfig::builder("./tests/layout.ksm")
+        .with_layout("./tests/layout/order.txt")
+        .with_keep_symbol("first")
+        .with_keep_symbol("second")
+        .with_keep_symbol("third")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", None));
+    driver.add_file(String::from("first.ko"), func_ko("first", None));
+    driver.add_file(String::from("second.ko"), func_ko("second", None));
+    driver.add_file(String::from("third.ko"), func_ko("third", None));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link with a layout file");
+    }
+
+    let offsets = driver.function_offsets();
+
+    // _start still comes first, and second isn't listed so it comes after everything that is
+    assert_eq!(offsets["_start"], 1);
+    assert_eq!(offsets["third"], 3);
+    assert_eq!(offsets["first"], 5);
+    assert_eq!(offsets["second"], 7);
+
+    let warnings: Vec<&LinkWarning> = driver.warnings().collect();
+
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0],
+        LinkWarning::LayoutFunctionWarning(name) if name == "missing"
+    ));
+}
+
+#[test]
+fn missing_layout_file() {
+    let config = CLIConfig::builder("./tests/layout-missing.ksm")
+        .with_layout("./tests/layout/missing.txt")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", None));
+
+    match driver.link() {
+        Err(LinkError::IOError(file_name, _)) => {
+            assert_eq!(file_name, "./tests/layout/missing.txt")
+        }
+        Err(e) => panic!("Expected an I/O error, found: {}", e),
+        Ok(_) => panic!("Linking with a missing layout file should fail"),
+    }
+}
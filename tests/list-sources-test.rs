@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ko::{symbols::KOSymbol, KOFile};
+use klinker::driver::errors::LinkError;
+use klinker::{list_sources, ObjectSource};
+
+mod common;
+
+use common::write_ko_file;
+
+#[test]
+fn list_source_and_comments() {
+    write_ko(
+        "./tests/list-sources-main.ko",
+        "main.kasm",
+        &["compiled by kOS 1.4", "assembled by kasm 2.0"],
+    );
+    write_ko("./tests/list-sources-lib.ko", "lib.kasm", &[]);
+
+    let sources = match list_sources(&[
+        PathBuf::from("./tests/list-sources-main.ko"),
+        PathBuf::from("./tests/list-sources-lib.ko"),
+    ]) {
+        Ok(sources) => sources,
+        Err(e) => panic!("Failed to list sources: {}", e),
+    };
+
+    assert_eq!(
+        sources,
+        vec![
+            ObjectSource {
+                input_file_name: String::from("list-sources-main.ko"),
+                source_file_name: String::from("main.kasm"),
+                comment: Some(String::from("compiled by kOS 1.4\nassembled by kasm 2.0")),
+            },
+            ObjectSource {
+                input_file_name: String::from("list-sources-lib.ko"),
+                source_file_name: String::from("lib.kasm"),
+                comment: None,
+            },
+        ]
+    );
+
+    assert_eq!(
+        sources[0].to_string(),
+        "list-sources-main.ko: main.kasm\n    compiled by kOS 1.4\n    assembled by kasm 2.0\n"
+    );
+}
+
+#[test]
+fn list_sources_missing_file() {
+    match list_sources(&[PathBuf::from("./tests/does-not-exist.ko")]) {
+        Err(LinkError::IOError(..)) => {}
+        Err(e) => panic!("Expected an I/O error, found: {}", e),
+        Ok(_) => panic!("Listing a file that doesn't exist should fail"),
+    }
+}
+
+/// Writes a KO file with nothing but a file symbol and the given comments. It has no .data
+/// section, so it couldn't be linked, but its source can still be listed.
+fn write_ko(path: &str, source_file_name: &str, comments: &[&str]) {
+    let mut ko = KOFile::new();
+
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add(source_file_name),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    ));
+
+    if !comments.is_empty() {
+        let mut comment_section = ko.new_strtab(".comment");
+
+        for comment in comments {
+            comment_section.add(*comment);
+        }
+
+        ko.add_str_tab(comment_section);
+    }
+
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, path);
+}
@@ -0,0 +1,175 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+const HELPER_COUNT: usize = 1;
+
+#[test]
+fn extern_satisfied_by_local() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("main.ko"), main_ko());
+    driver.add_file(String::from("helper0.ko"), helper_ko(0, SymBind::Local));
+
+    match driver.link() {
+        Err(LinkError::LocalDefinitionError(name, file_name)) => {
+            assert_eq!(name, "helper0");
+            assert_eq!(file_name, "helper0.ko");
+        }
+        Err(e) => panic!("Expected a local definition error, found: {}", e),
+        Ok(_) => panic!("A local function should not satisfy an external symbol"),
+    }
+}
+
+#[test]
+fn extern_without_definition() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    match driver.link() {
+        Err(LinkError::UnresolvedExternalSymbolError(name)) => assert_eq!(name, "helper0"),
+        Err(e) => panic!("Expected an unresolved external symbol error, found: {}", e),
+        Ok(_) => panic!("An external symbol with no definition should fail"),
+    }
+
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("main.ko"), main_ko());
+    driver.add_file(String::from("helper0.ko"), helper_ko(0, SymBind::Global));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("A global function should satisfy an external symbol");
+    }
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/local-extern.ksm").build()
+}
+
+/// Creates a KO file whose _start calls every helper in reverse order
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    for i in (0..HELPER_COUNT).rev() {
+        let helper_symbol = KOSymbol::new(
+            symstrtab.add(format!("helper{}", i)),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let helper_symbol_index = symtab.add(helper_symbol);
+
+        let call_instr = start.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            helper_symbol_index,
+        ));
+    }
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a single function helper{index} that returns its own index
+fn helper_ko(index: usize, sym_bind: SymBind) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let func_name = format!("helper{}", index);
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(&func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let index_value = data_section.add(KOSValue::Int16(index as i16));
+
+    func.add(Instr::OneOp(Opcode::Ret, index_value));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(&func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        sym_bind,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
@@ -1,5 +1,4 @@
 use std::io::{Read, Write};
-use std::path::PathBuf;
 
 use kerbalobjects::ko::sections::DataIdx;
 use kerbalobjects::ko::symbols::OperandIndex;
@@ -11,8 +10,13 @@ use kerbalobjects::{
     },
     BufferIterator, KOSValue, Opcode,
 };
+use klinker::driver::log::LogLevel;
 use klinker::{driver::Driver, CLIConfig};
 
+mod common;
+
+use common::LinkedProgram;
+
 #[test]
 fn link_with_locals() {
     write_main();
@@ -55,13 +59,10 @@ fn link_with_locals() {
 
     let intlib_ko = KOFile::parse(&mut buffer_iter).expect("Error reading KO file");
 
-    let config = CLIConfig {
-        input_paths: Vec::new(),
-        output_path: PathBuf::from("./tests/locals.ksm"),
-        entry_point: String::from("_start"),
-        shared: false,
-        debug: true,
-    };
+    let config = CLIConfig::builder("./tests/locals.ksm")
+        .with_validate(true)
+        .with_verbosity(LogLevel::MAX)
+        .build();
 
     let mut driver = Driver::new(config);
 
@@ -80,6 +81,13 @@ fn link_with_locals() {
 
             file.write_all(file_buffer.as_slice())
                 .expect("Cannot write locals.ksm");
+
+            let program = LinkedProgram::parse(&file_buffer);
+
+            // Both libraries are called through labels
+            assert!(!program.labels().is_empty());
+
+            program.assert_labels(driver.function_offsets().into_values());
         }
         Err(e) => {
             eprintln!("{}", e);
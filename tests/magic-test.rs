@@ -0,0 +1,139 @@
+use std::io::Read;
+
+use clap::Parser;
+use flate2::read::GzDecoder;
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::{driver::Driver, write_ksm, write_ksm_to, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn write_custom_magic() {
+    let mut driver = Driver::new(CLIConfig::builder("./tests/magic.ksm").build());
+
+    driver.add_file(String::from("start.ko"), start_ko());
+
+    let ksm_file = driver.link().expect("Failed to link");
+
+    let mut standard = Vec::new();
+    ksm_file.write(&mut standard);
+
+    assert_eq!(write_ksm(&ksm_file, None), standard);
+
+    let standard = decompress(&standard);
+    let custom = decompress(&write_ksm(&ksm_file, Some(0x1234_5678)));
+
+    // Only the header changes
+    assert_eq!(custom[..4], [0x78, 0x56, 0x34, 0x12]);
+    assert_eq!(custom[4..], standard[4..]);
+}
+
+#[test]
+fn stream_ksm() {
+    let mut driver = Driver::new(CLIConfig::builder("./tests/stream.ksm").build());
+
+    driver.add_file(String::from("start.ko"), start_ko());
+
+    let ksm_file = driver.link().expect("Failed to link");
+
+    // Compressing each section as it is written gives exactly the same file
+    let mut standard = Vec::new();
+    ksm_file.write(&mut standard);
+
+    let streamed = write_ksm_to(&ksm_file, None, Vec::new()).expect("Failed to stream KSM file");
+
+    assert_eq!(streamed, standard);
+
+    let streamed =
+        write_ksm_to(&ksm_file, Some(0x1234_5678), Vec::new()).expect("Failed to stream KSM file");
+
+    assert_eq!(streamed, write_ksm(&ksm_file, Some(0x1234_5678)));
+}
+
+#[test]
+fn parse_magic_argument() {
+    let config = CLIConfig::parse_from([
+        "kld",
+        "main.ko",
+        "-o",
+        "main.ksm",
+        "--ksm-magic",
+        "0x4558036C",
+    ]);
+
+    assert_eq!(config.ksm_magic, Some(0x4558_036c));
+
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm", "--ksm-magic", "42"]);
+
+    assert_eq!(config.ksm_magic, Some(42));
+
+    assert!(CLIConfig::try_parse_from([
+        "kld",
+        "main.ko",
+        "-o",
+        "main.ksm",
+        "--ksm-magic",
+        "0xnope"
+    ])
+    .is_err());
+}
+
+fn decompress(bytes: &[u8]) -> Vec<u8> {
+    let mut contents = Vec::new();
+
+    GzDecoder::new(bytes)
+        .read_to_end(&mut contents)
+        .expect("Could not decompress KSM file");
+
+    contents
+}
+
+/// Creates a KO file with only a _start function
+fn start_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let value_index = data_section.add(KOSValue::Int16(42));
+
+    start.add(Instr::OneOp(Opcode::Push, value_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("start.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
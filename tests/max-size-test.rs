@@ -0,0 +1,80 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{run, CLIConfig};
+
+mod common;
+
+use common::write_ko_file;
+
+#[test]
+fn reject_oversized_output() {
+    write_start_ko("./tests/max-size.ko");
+
+    let config = CLIConfig::builder("./tests/max-size.ksm")
+        .with_input_path("./tests/max-size.ko")
+        .with_max_size(16)
+        .build();
+
+    match run(&config) {
+        Err(LinkError::OutputTooLargeError(_, max_size, _)) => assert_eq!(max_size, 16),
+        Err(e) => panic!("Expected an output too large error, found: {}", e),
+        Ok(_) => panic!("Output larger than --max-size should fail"),
+    }
+
+    let config = CLIConfig {
+        max_size: Some(4096),
+        ..config
+    };
+
+    if let Err(e) = run(&config) {
+        eprintln!("{}", e);
+        panic!("Output within --max-size should link");
+    }
+}
+
+fn write_start_ko(path: &str) {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("max-size.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, path);
+}
@@ -0,0 +1,290 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::sections::{
+    ArgumentSection, CodeSection, CodeType, DebugEntry, DebugRange, DebugSection,
+};
+use kerbalobjects::ksm::{self, KSMFile};
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::driver::validate::validate;
+use klinker::{driver::Driver, merge_ksm, merge_ksm_keeping_entries, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn merge_rebases_labels() {
+    let base = link_base();
+    let addon = link_addon();
+
+    let base_length = total_length(&base);
+    let addon_length = total_length(&addon);
+
+    let merged = merge_ksm(base, addon).expect("Failed to merge KSM files");
+
+    validate(&merged).expect("Merged file is not valid");
+
+    // Each file's label reset is replaced by a single one
+    assert_eq!(total_length(&merged), base_length + addon_length - 1);
+
+    let instrs: Vec<(CodeType, &ksm::Instr)> = merged
+        .code_sections()
+        .flat_map(|code_section| {
+            code_section
+                .instructions()
+                .map(|instr| (code_section.section_type, instr))
+        })
+        .collect();
+
+    // Every call still goes to the function it went to before, which each push their own value
+    let mut called_values: Vec<(CodeType, i16)> = instrs
+        .iter()
+        .filter_map(|(section_type, instr)| match instr {
+            ksm::Instr::TwoOp(Opcode::Call, label, _) => Some((*section_type, *label)),
+            _ => None,
+        })
+        .map(|(section_type, label)| {
+            let target = match merged.arg_section.get(label) {
+                Some(KOSValue::String(label)) => label[1..].parse::<usize>().unwrap(),
+                value => panic!("Call to {:?} instead of a label", value),
+            };
+
+            let pushed = match instrs[target].1 {
+                ksm::Instr::OneOp(Opcode::Push, op) => merged.arg_section.get(*op),
+                instr => panic!("Call to {:?} instead of a function", instr),
+            };
+
+            match pushed {
+                Some(KOSValue::Int16(value)) => (section_type, *value),
+                value => panic!("Function pushes {:?}", value),
+            }
+        })
+        .collect();
+
+    called_values.sort_by_key(|(_, value)| *value);
+
+    assert_eq!(
+        called_values,
+        vec![
+            (CodeType::Main, 1),
+            (CodeType::Initialization, 2),
+            (CodeType::Function, 3)
+        ]
+    );
+}
+
+#[test]
+fn reject_two_entry_points() {
+    match merge_ksm(link_base(), link_base()) {
+        Err(LinkError::MergeEntryPointError) => {}
+        Err(e) => panic!("Expected a merge entry point error, found: {}", e),
+        Ok(_) => panic!("Merging two files with entry points should fail"),
+    }
+
+    let merged =
+        merge_ksm_keeping_entries(link_base(), link_base()).expect("Failed to merge KSM files");
+
+    validate(&merged).expect("Merged file is not valid");
+}
+
+#[test]
+fn reject_unlinked_file() {
+    let mut arg_section = ArgumentSection::new();
+    let zero_index = arg_section.add(KOSValue::Int16(0));
+
+    let main = CodeSection::new(CodeType::Main)
+        .with_instructions([ksm::Instr::OneOp(Opcode::Push, zero_index)]);
+
+    let unlinked = KSMFile::new_from_parts(
+        arg_section,
+        vec![
+            CodeSection::new(CodeType::Function),
+            CodeSection::new(CodeType::Initialization),
+            main,
+        ],
+        DebugSection::new(DebugEntry::new(1).with_range(DebugRange::new(2, 4))),
+    );
+
+    match merge_ksm(link_base(), unlinked) {
+        Err(LinkError::MergeError(_)) => {}
+        Err(e) => panic!("Expected a merge error, found: {}", e),
+        Ok(_) => panic!("A file without a label reset can't be merged"),
+    }
+}
+
+#[test]
+fn keep_label_like_data() {
+    let mut arg_section = ArgumentSection::new();
+
+    let begin = arg_section.add(KOSValue::String(String::from("@0001")));
+    let in_range = arg_section.add(KOSValue::String(String::from("@0003")));
+    let out_of_range = arg_section.add(KOSValue::String(String::from("@0099")));
+    let zero = arg_section.add(KOSValue::Int16(0));
+
+    let functions = CodeSection::new(CodeType::Function).with_instructions([
+        ksm::Instr::OneOp(Opcode::Lbrt, begin),
+        ksm::Instr::OneOp(Opcode::Push, in_range),
+        ksm::Instr::OneOp(Opcode::Push, out_of_range),
+        ksm::Instr::OneOp(Opcode::Ret, zero),
+    ]);
+
+    let addon = KSMFile::new_from_parts(
+        arg_section,
+        vec![
+            functions,
+            CodeSection::new(CodeType::Initialization),
+            CodeSection::new(CodeType::Main),
+        ],
+        DebugSection::new(DebugEntry::new(1).with_range(DebugRange::new(2, 4))),
+    );
+
+    let merged = merge_ksm(link_base(), addon).expect("Failed to merge KSM files");
+
+    validate(&merged).expect("Merged file is not valid");
+
+    // Only what is jumped or called to is a label, so pushed strings are left alone
+    let pushed: Vec<&KOSValue> = merged
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions())
+        .filter_map(|instr| match instr {
+            ksm::Instr::OneOp(Opcode::Push, op) => merged.arg_section.get(*op),
+            _ => None,
+        })
+        .filter(|value| matches!(value, KOSValue::String(_)))
+        .collect();
+
+    assert_eq!(
+        pushed,
+        vec![
+            &KOSValue::String(String::from("@0003")),
+            &KOSValue::String(String::from("@0099"))
+        ]
+    );
+}
+
+fn total_length(ksm_file: &KSMFile) -> usize {
+    Driver::code_section_lengths(ksm_file)
+        .into_iter()
+        .map(|(_, length)| length)
+        .sum()
+}
+
+/// An executable whose _start calls helper, which pushes 1
+fn link_base() -> KSMFile {
+    let mut driver = Driver::new(CLIConfig::builder("./tests/merge-base.ksm").build());
+
+    driver.add_file(
+        String::from("start.ko"),
+        func_ko("_start", Some("helper"), 0),
+    );
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None, 1));
+
+    driver.link().expect("Failed to link the base")
+}
+
+/// A shared object whose _init calls plugin, which pushes 2 and calls plugin_helper, which pushes 3
+fn link_addon() -> KSMFile {
+    let config = CLIConfig::builder("./tests/merge-addon.ksm")
+        .with_shared(true)
+        .with_split_sections(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("init.ko"), func_ko("_init", Some("plugin"), 0));
+    driver.add_file(
+        String::from("plugin.ko"),
+        func_ko("plugin", Some("plugin_helper"), 2),
+    );
+    driver.add_file(
+        String::from("plugin_helper.ko"),
+        func_ko("plugin_helper", None, 3),
+    );
+
+    driver.link().expect("Failed to link the add-on")
+}
+
+/// Creates a KO file with a single global function that pushes the given value, after calling
+/// another global function if one is given
+fn func_ko(func_name: &str, callee: Option<&str>, value: i16) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let value_index = data_section.add(KOSValue::Int16(value));
+
+    // Functions begin with their push, so that what a call reaches can be told apart
+    func.add(Instr::OneOp(Opcode::Push, value_index));
+
+    if let Some(callee) = callee {
+        let callee_symbol = KOSymbol::new(
+            symstrtab.add(callee),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let callee_symbol_index = symtab.add(callee_symbol);
+
+        let call_instr = func.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            func.section_index(),
+            call_instr,
+            OperandIndex::One,
+            callee_symbol_index,
+        ));
+    }
+
+    if func_name == "_start" {
+        func.add(Instr::ZeroOp(Opcode::Eop));
+    } else {
+        func.add(Instr::OneOp(Opcode::Ret, value_index));
+    }
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,180 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, ReldEntry, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{ko::symbols::KOSymbol, ko::Instr, ko::KOFile, KOSValue, Opcode};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn missing_data_section_hint() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("nodata.ko"), no_data_ko());
+
+    match driver.link() {
+        Err(e @ LinkError::MissingSectionError(..)) => {
+            let message = e.to_string();
+
+            assert!(message.contains(".data"));
+            assert!(message.contains("kasm"));
+        }
+        Err(e) => panic!("Expected a missing section error, found: {}", e),
+        Ok(_) => panic!("Linking a file without a .data section should fail"),
+    }
+}
+
+#[test]
+fn missing_function_symbol() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("orphan.ko"), orphan_function_ko());
+
+    match driver.link() {
+        Err(LinkError::FuncContextError(_, e @ ProcessingError::FuncMissingSymbolError(..))) => {
+            let message = e.to_string();
+
+            assert!(message.contains("orphan"));
+            assert!(message.contains("type Func"));
+        }
+        Err(e) => panic!("Expected a missing function symbol error, found: {}", e),
+        Ok(_) => panic!("Linking a function section without a symbol should fail"),
+    }
+}
+
+#[test]
+fn function_symbol_without_section() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("ghost.ko"), ghost_function_ko());
+
+    match driver.link() {
+        Err(LinkError::InternalError(message)) => {
+            assert!(message.contains("has no offset"));
+        }
+        Err(e) => panic!("Expected an internal error, found: {}", e),
+        Ok(_) => panic!("Calling a function with no section should fail"),
+    }
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/missing.ksm").build()
+}
+
+/// Creates a KO file that only has a symbol table, as if it had not been produced by kasm
+fn no_data_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("nodata.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+    symtab.add(file_symbol);
+
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a function section that has no symbol of the same name
+fn orphan_function_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut orphan = ko.new_func_section("orphan");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    orphan.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add("orphan.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    ));
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(orphan);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file whose _start calls a global function symbol that has no function section
+fn ghost_function_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let ghost_symbol_index = symtab.add(KOSymbol::new(
+        symstrtab.add("ghost"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::Func,
+        data_section.section_index(),
+    ));
+
+    let call_instr = start.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    reld_section.add(ReldEntry::new(
+        start.section_index(),
+        call_instr,
+        OperandIndex::One,
+        ghost_symbol_index,
+    ));
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    ));
+    symtab.add(KOSymbol::new(
+        symstrtab.add("ghost.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    ));
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,271 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::Instr as KSMInstr;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, LinkWarning, ProcessingError};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn last_definition_wins() {
+    let mut driver = Driver::new(config(true));
+
+    driver.add_file(String::from("main.ko"), main_ko());
+    driver.add_file(String::from("lib.ko"), lib_ko("lib.kasm", 1, 10));
+    driver.add_file(String::from("stub.ko"), lib_ko("stub.kasm", 2, 20));
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with multiple definitions allowed");
+        }
+    };
+
+    let instructions: Vec<KSMInstr> = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions().copied())
+        .collect();
+
+    // Only the stub's copy of helper should have been emitted
+    let returned: Vec<&KOSValue> = instructions
+        .iter()
+        .filter_map(|instr| match instr {
+            KSMInstr::OneOp(Opcode::Ret, op) => ksm_file.arg_section.get(*op),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(returned, vec![&KOSValue::Int16(2)]);
+
+    // And number should refer to the stub's value
+    let pushed: Vec<&KOSValue> = instructions
+        .iter()
+        .filter_map(|instr| match instr {
+            KSMInstr::OneOp(Opcode::Push, op) => ksm_file.arg_section.get(*op),
+            _ => None,
+        })
+        .collect();
+
+    assert!(pushed.contains(&&KOSValue::ScalarInt(20)));
+    assert!(!pushed.contains(&&KOSValue::ScalarInt(10)));
+
+    let overridden: Vec<String> = driver
+        .warnings()
+        .filter_map(|warning| match warning {
+            LinkWarning::MultipleDefinitionWarning(name, original, replacement) => {
+                assert_eq!(original.source_file_name, "lib.kasm");
+                assert_eq!(replacement.source_file_name, "stub.kasm");
+
+                Some(name.to_owned())
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(overridden, vec!["helper", "number"]);
+}
+
+#[test]
+fn multiple_definitions_rejected_by_default() {
+    let mut driver = Driver::new(config(false));
+
+    driver.add_file(String::from("main.ko"), main_ko());
+    driver.add_file(String::from("lib.ko"), lib_ko("lib.kasm", 1, 10));
+    driver.add_file(String::from("stub.ko"), lib_ko("stub.kasm", 2, 20));
+
+    match driver.link() {
+        Err(LinkError::FileContextError(_, ProcessingError::DuplicateSymbolError(name, ..))) => {
+            assert_eq!(name, "helper");
+        }
+        Err(e) => panic!("Expected a duplicate symbol error, found: {}", e),
+        Ok(_) => panic!("Linking a duplicate symbol should fail"),
+    }
+}
+
+#[test]
+fn trace_overridden_symbol() {
+    let config = CLIConfig::builder("./tests/multiple-definition.ksm")
+        .with_allow_multiple_definition(true)
+        .with_trace_symbol("number")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko());
+    driver.add_file(String::from("lib.ko"), lib_ko("lib.kasm", 1, 10));
+    driver.add_file(String::from("stub.ko"), lib_ko("stub.kasm", 2, 20));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link with multiple definitions allowed");
+    }
+
+    assert_eq!(
+        driver.symbol_trace(),
+        [
+            "main.ko: reference to number",
+            "lib.ko: definition of number",
+            "stub.ko: definition of number",
+            "stub.ko: number replaces the definition in lib.ko",
+            "number resolved to the definition in stub.ko",
+        ]
+    );
+}
+
+fn config(allow_multiple_definition: bool) -> CLIConfig {
+    config_builder("./tests/multiple-definition.ksm")
+        .with_allow_multiple_definition(allow_multiple_definition)
+        .with_validate(true)
+        .build()
+}
+
+/// Creates a KO file whose _start pushes the global variable number and calls the global function
+/// helper
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let helper_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Extern,
+        SymType::Func,
+        data_section.section_index(),
+    );
+    let helper_symbol_index = symtab.add(helper_symbol);
+
+    let number_symbol = KOSymbol::new(
+        symstrtab.add("number"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Extern,
+        SymType::NoType,
+        data_section.section_index(),
+    );
+    let number_symbol_index = symtab.add(number_symbol);
+
+    let push_instr = start.add(Instr::OneOp(Opcode::Push, DataIdx::PLACEHOLDER));
+    let call_instr = start.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    reld_section.add(ReldEntry::new(
+        start.section_index(),
+        push_instr,
+        OperandIndex::One,
+        number_symbol_index,
+    ));
+    reld_section.add(ReldEntry::new(
+        start.section_index(),
+        call_instr,
+        OperandIndex::One,
+        helper_symbol_index,
+    ));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a global function helper that returns helper_value, and a global
+/// variable number that holds number_value
+fn lib_ko(source_file_name: &str, helper_value: i16, number_value: i32) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section("helper");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let helper_value_index = data_section.add(KOSValue::Int16(helper_value));
+
+    let number_value = KOSValue::ScalarInt(number_value);
+    let number_value_size = number_value.size_bytes();
+    let number_value_index = data_section.add(number_value);
+
+    func.add(Instr::OneOp(Opcode::Ret, helper_value_index));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let number_symbol = KOSymbol::new(
+        symstrtab.add("number"),
+        number_value_index,
+        number_value_size as u16,
+        SymBind::Global,
+        SymType::NoType,
+        data_section.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(source_file_name),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+    symtab.add(number_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
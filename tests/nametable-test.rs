@@ -0,0 +1,36 @@
+use klinker::tables::{NameTable, NameTableEntry};
+
+#[test]
+fn insert_reports_fresh_entries() {
+    let mut table = NameTable::<u8>::new();
+
+    let (first, inserted) = table.insert(NameTableEntry::from(String::from("_start"), 1));
+    assert!(inserted);
+
+    let (second, inserted) = table.insert(NameTableEntry::from(String::from("helper"), 2));
+    assert!(inserted);
+    assert_ne!(first, second);
+
+    // The existing entry is kept, and its position is returned
+    let (again, inserted) = table.insert(NameTableEntry::from(String::from("_start"), 3));
+    assert!(!inserted);
+    assert_eq!(again, first);
+    assert_eq!(*table.get_at(first).unwrap().value(), 1);
+}
+
+#[test]
+fn raw_insert_reports_fresh_entries() {
+    let mut table = NameTable::<u8>::new();
+
+    let (first, inserted) = table.raw_insert(42, NameTableEntry::from(String::from("local"), 1));
+    assert!(inserted);
+
+    // Only the hash matters, so the same name can be stored under another hash
+    let (second, inserted) = table.raw_insert(43, NameTableEntry::from(String::from("local"), 2));
+    assert!(inserted);
+    assert_ne!(first, second);
+
+    let (again, inserted) = table.raw_insert(42, NameTableEntry::from(String::from("other"), 3));
+    assert!(!inserted);
+    assert_eq!(again, first);
+}
@@ -0,0 +1,92 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn allow_unused_extern() {
+    let mut driver = Driver::new(config(false));
+
+    driver.add_file(String::from("main.ko"), unused_extern_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("An unused external symbol should only fail with --no-undefined");
+    }
+}
+
+#[test]
+fn reject_unused_extern() {
+    let mut driver = Driver::new(config(true));
+
+    driver.add_file(String::from("main.ko"), unused_extern_ko());
+
+    match driver.link() {
+        Err(LinkError::UnresolvedExternalSymbolError(name)) => assert_eq!(name, "missing"),
+        Err(e) => panic!("Expected an unresolved external symbol error, found: {}", e),
+        Ok(_) => panic!("An unused external symbol should fail with --no-undefined"),
+    }
+}
+
+fn config(no_undefined: bool) -> CLIConfig {
+    config_builder("./tests/no-undefined.ksm")
+        .with_no_undefined(no_undefined)
+        .with_verbosity(LogLevel::MAX)
+        .build()
+}
+
+/// Creates a KO file that declares an external symbol that none of its instructions use
+fn unused_extern_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add("missing"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Extern,
+        SymType::NoType,
+        data_section.section_index(),
+    ));
+    symtab.add(KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    ));
+    symtab.add(KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    ));
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
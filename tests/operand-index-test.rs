@@ -0,0 +1,118 @@
+use kerbalobjects::ko::sections::{DataIdx, SymbolIdx};
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn report_invalid_data_operand() {
+    match link(bad_operand_ko(false)) {
+        Err(LinkError::FuncContextError(ctx, e)) => {
+            assert!(matches!(
+                e,
+                ProcessingError::InvalidDataIndexError(1, 2, 99)
+            ));
+            assert_eq!(ctx.func_name, "_start");
+            assert_eq!(
+                e.to_string(),
+                "Instruction number 1, operand 2 references invalid data index 99"
+            );
+        }
+        Err(e) => panic!("Expected an invalid data index error, found: {}", e),
+        Ok(_) => panic!("An operand past the end of the data section should fail"),
+    }
+}
+
+#[test]
+fn report_invalid_symbol_operand() {
+    match link(bad_operand_ko(true)) {
+        Err(LinkError::FuncContextError(_, e)) => {
+            assert!(matches!(
+                e,
+                ProcessingError::InvalidSymbolIndexError(1, 2, 99)
+            ));
+            assert_eq!(
+                e.to_string(),
+                "Instruction number 1, operand 2 references invalid symbol index 99"
+            );
+        }
+        Err(e) => panic!("Expected an invalid symbol index error, found: {}", e),
+        Ok(_) => panic!("A relocation to a symbol that doesn't exist should fail"),
+    }
+}
+
+fn link(main: KOFile) -> Result<kerbalobjects::ksm::KSMFile, LinkError> {
+    let mut driver = Driver::new(CLIConfig::builder("./tests/operand-index.ksm").build());
+
+    driver.add_file(String::from("main.ko"), main);
+
+    driver.link()
+}
+
+/// Creates a KO file whose _start has an instruction with a valid first operand, and a second
+/// operand that either has a data index or a relocation to a symbol index that doesn't exist
+fn bad_operand_ko(bad_symbol: bool) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+
+    let bad_instr = start.add(Instr::TwoOp(Opcode::Call, zero_index, DataIdx::from(99u32)));
+
+    if bad_symbol {
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            bad_instr,
+            OperandIndex::Two,
+            SymbolIdx::from(99u32),
+        ));
+    }
+
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
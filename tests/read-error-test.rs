@@ -0,0 +1,121 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::driver::reader::Reader;
+
+mod common;
+
+use common::write_ko;
+
+#[test]
+fn truncated_in_section() {
+    let bytes = start_ko_bytes();
+
+    // .symstrtab is the last section, so this cuts into the end of .symtab
+    let truncated = &bytes[..bytes.len() - SYMSTRTAB_SIZE - 2];
+
+    let error = match Reader::read_bytes("start.ko", truncated) {
+        Err(e) => e,
+        Ok(_) => panic!("Reading a truncated file should fail"),
+    };
+
+    assert!(error
+        .to_string()
+        .contains(&format!("in .symtab at byte {}", truncated.len())));
+
+    match error {
+        LinkError::FileReadError(_, _, offset, section) => {
+            assert_eq!(offset, truncated.len());
+            assert_eq!(section.as_deref(), Some(".symtab"));
+        }
+        e => panic!("Expected a file read error, found: {}", e),
+    }
+}
+
+#[test]
+fn truncated_in_header() {
+    let bytes = start_ko_bytes();
+
+    match Reader::read_bytes("start.ko", &bytes[..6]) {
+        Err(LinkError::FileReadError(_, _, offset, section)) => {
+            assert_eq!(offset, 6);
+            assert_eq!(section.as_deref(), Some("the file header"));
+        }
+        Err(e) => panic!("Expected a file read error, found: {}", e),
+        Ok(_) => panic!("Reading a truncated file should fail"),
+    }
+}
+
+#[test]
+fn empty_file() {
+    match Reader::read_file("./tests/empty.ko") {
+        Err(LinkError::EmptyInputFileError(file_name)) => assert_eq!(file_name, "empty.ko"),
+        Err(e) => panic!("Expected an empty input file error, found: {}", e),
+        Ok(_) => panic!("Reading an empty file should fail"),
+    }
+}
+
+#[test]
+fn three_byte_file() {
+    // Only the first 3 bytes of the magic number
+    match Reader::read_file("./tests/truncated.ko") {
+        Err(LinkError::FileReadError(file_name, _, offset, section)) => {
+            assert_eq!(file_name, "truncated.ko");
+            assert_eq!(offset, 3);
+            assert_eq!(section.as_deref(), Some("the file header"));
+        }
+        Err(e) => panic!("Expected a file read error, found: {}", e),
+        Ok(_) => panic!("Reading a truncated file should fail"),
+    }
+}
+
+/// The size of the .symstrtab written by start_ko_bytes: a leading null, then "_start" and
+/// "start.kasm" each with their null terminator
+const SYMSTRTAB_SIZE: usize = 1 + 7 + 11;
+
+/// Writes a KO file with a _start function, with its sections in the order they are created
+fn start_ko_bytes() -> Vec<u8> {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("start.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko(ko)
+}
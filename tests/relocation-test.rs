@@ -0,0 +1,121 @@
+use kerbalobjects::ko::sections::{DataIdx, InstrIdx};
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn reject_relocation_past_last_operand() {
+    let config = config_builder("./tests/relocation.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("corrupt.ko"), corrupt_ko(false));
+
+    match driver.link() {
+        Err(LinkError::FuncContextError(
+            ctx,
+            ProcessingError::InvalidRelocationOperandError(instr_index, operand_index),
+        )) => {
+            assert_eq!(ctx.func_name, "_start");
+            assert_eq!(instr_index, 0);
+            assert_eq!(operand_index, 2);
+        }
+        Err(e) => panic!("Expected an invalid relocation error, found: {}", e),
+        Ok(_) => panic!("Linking a corrupt relocation should fail"),
+    }
+}
+
+#[test]
+fn reject_relocation_past_last_instruction() {
+    let config = CLIConfig::builder("./tests/relocation-past-end.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("corrupt.ko"), corrupt_ko(true));
+
+    match driver.link() {
+        Err(LinkError::FuncContextError(
+            ctx,
+            ProcessingError::InvalidRelocationInstrError(instr_index, instr_count),
+        )) => {
+            assert_eq!(ctx.func_name, "_start");
+            assert_eq!(instr_index, 5);
+            assert_eq!(instr_count, 2);
+        }
+        Err(e) => panic!("Expected an invalid relocation error, found: {}", e),
+        Ok(_) => panic!("Linking a corrupt relocation should fail"),
+    }
+}
+
+/// Creates a KO file with a relocation of the second operand of a one-operand instruction, or if
+/// past_end is set, of an instruction after the end of the function
+fn corrupt_ko(past_end: bool) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let push_instr = start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("corrupt.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    let start_symbol_index = symtab.add(start_symbol);
+
+    if past_end {
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            InstrIdx::from(5u32),
+            OperandIndex::One,
+            start_symbol_index,
+        ));
+    } else {
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            push_instr,
+            OperandIndex::Two,
+            start_symbol_index,
+        ));
+    }
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
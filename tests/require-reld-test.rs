@@ -0,0 +1,113 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn require_reld_with_externs() {
+    let mut driver = Driver::new(config("./tests/require-reld.ksm", true));
+
+    driver.add_file(String::from("start.ko"), start_ko(Some("helper")));
+
+    match driver.link() {
+        Err(LinkError::MissingSectionError(file_name, section_name)) => {
+            assert_eq!(file_name, "start.ko");
+            assert_eq!(section_name, ".reld");
+        }
+        Err(e) => panic!("Expected a missing section error, found: {}", e),
+        Ok(_) => panic!("A file with externs and no .reld section should be rejected"),
+    }
+
+    // Without the flag the same file links
+    let mut driver = Driver::new(config("./tests/require-reld-off.ksm", false));
+
+    driver.add_file(String::from("start.ko"), start_ko(Some("helper")));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link without --require-reld");
+    }
+}
+
+#[test]
+fn require_reld_without_symbols() {
+    let mut driver = Driver::new(config("./tests/require-reld-none.ksm", true));
+
+    // Nothing in this file could be relocated, so it doesn't need a .reld section
+    driver.add_file(String::from("start.ko"), start_ko(None));
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link a file that has no symbols to relocate");
+    }
+}
+
+fn config(output_path: &str, require_reld: bool) -> CLIConfig {
+    config_builder(output_path)
+        .with_require_reld(require_reld)
+        .build()
+}
+
+/// Creates a KO file with a _start function and no .reld section, which declares an external
+/// symbol if one is given
+fn start_ko(extern_name: Option<&str>) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    if let Some(extern_name) = extern_name {
+        let extern_symbol = KOSymbol::new(
+            symstrtab.add(extern_name),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        symtab.add(extern_symbol);
+    }
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("start.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
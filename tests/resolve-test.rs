@@ -0,0 +1,364 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+const HELPER_COUNT: usize = 32;
+
+#[test]
+fn resolution_is_deterministic() {
+    let first = link_helpers();
+
+    for _ in 0..4 {
+        assert_eq!(first, link_helpers());
+    }
+}
+
+#[test]
+fn duplicate_detected_in_later_file() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    for i in 0..HELPER_COUNT {
+        driver.add_file(format!("helper{}.ko", i), helper_ko(i));
+    }
+
+    // Redefines helper0, which is only an error once every other file has been resolved
+    driver.add_file(String::from("duplicate.ko"), helper_ko(0));
+
+    match driver.link() {
+        Err(LinkError::FileContextError(
+            _,
+            error @ ProcessingError::DuplicateSymbolError(_, original_type, duplicate_type, ..),
+        )) => {
+            assert!(
+                matches!(&error, ProcessingError::DuplicateSymbolError(name, ..) if name == "helper0")
+            );
+            assert_eq!(original_type, SymType::Func);
+            assert_eq!(duplicate_type, SymType::Func);
+            assert!(error
+                .to_string()
+                .starts_with("Duplicate function 'helper0'"));
+        }
+        Err(e) => panic!("Expected a duplicate symbol error, found: {}", e),
+        Ok(_) => panic!("Linking a duplicate symbol should fail"),
+    }
+}
+
+#[test]
+fn duplicate_function_and_variable() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    for i in 0..HELPER_COUNT {
+        driver.add_file(format!("helper{}.ko", i), helper_ko(i));
+    }
+
+    // A variable that happens to share its name with a function
+    driver.add_file(String::from("variable.ko"), variable_ko("helper1"));
+
+    match driver.link() {
+        Err(LinkError::FileContextError(
+            _,
+            error @ ProcessingError::DuplicateSymbolError(_, original_type, duplicate_type, ..),
+        )) => {
+            assert!(
+                matches!(&error, ProcessingError::DuplicateSymbolError(name, ..) if name == "helper1")
+            );
+            assert_eq!(original_type, SymType::Func);
+            assert_eq!(duplicate_type, SymType::NoType);
+            assert!(error.to_string().contains("as a function"));
+            assert!(error.to_string().contains("as a variable"));
+        }
+        Err(e) => panic!("Expected a duplicate symbol error, found: {}", e),
+        Ok(_) => panic!("Linking a variable with the same name as a function should fail"),
+    }
+}
+
+#[test]
+fn no_input_files() {
+    let mut driver = Driver::new(config());
+
+    match driver.link() {
+        Err(LinkError::NoInputFiles) => {}
+        Err(e) => panic!("Expected a no input files error, found: {}", e),
+        Ok(_) => panic!("Linking nothing should fail"),
+    }
+}
+
+#[test]
+fn suggest_entry_point_casing() {
+    let config = CLIConfig {
+        entry_point: String::from("_Start"),
+        ..config()
+    };
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    for i in 0..HELPER_COUNT {
+        driver.add_file(format!("helper{}.ko", i), helper_ko(i));
+    }
+
+    match driver.link() {
+        Err(LinkError::MissingEntryPointError(entry_point, suggestion)) => {
+            assert_eq!(entry_point, "_Start");
+            assert_eq!(suggestion.as_deref(), Some("_start"));
+        }
+        Err(e) => panic!("Expected a missing entry point error, found: {}", e),
+        Ok(_) => panic!("The entry point should not be found with different casing"),
+    }
+}
+
+#[test]
+fn local_entry_point() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("local.ko"), local_start_ko());
+
+    match driver.link() {
+        Err(LinkError::LocalEntryPointError(entry_point, file_name)) => {
+            assert_eq!(entry_point, "_start");
+            assert_eq!(file_name, "local.ko");
+        }
+        Err(e) => panic!("Expected a local entry point error, found: {}", e),
+        Ok(_) => panic!("A local entry point should not be found"),
+    }
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/resolve.ksm").build()
+}
+
+/// Links main.ko against every helper and returns the bytes of the resulting KSM file
+fn link_helpers() -> Vec<u8> {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    for i in 0..HELPER_COUNT {
+        driver.add_file(format!("helper{}.ko", i), helper_ko(i));
+    }
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link helpers");
+        }
+    };
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    ksm_file.write(&mut file_buffer);
+
+    file_buffer
+}
+
+/// Creates a KO file whose _start calls every helper in reverse order
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    for i in (0..HELPER_COUNT).rev() {
+        let helper_symbol = KOSymbol::new(
+            symstrtab.add(format!("helper{}", i)),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let helper_symbol_index = symtab.add(helper_symbol);
+
+        let call_instr = start.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            helper_symbol_index,
+        ));
+    }
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a single global function helper{index} that returns its own index
+fn helper_ko(index: usize) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let func_name = format!("helper{}", index);
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(&func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let index_value = data_section.add(KOSValue::Int16(index as i16));
+
+    func.add(Instr::OneOp(Opcode::Ret, index_value));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(&func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a single global variable with the provided name
+fn variable_ko(name: &str) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let value = KOSValue::ScalarInt(32);
+    let value_size = value.size_bytes();
+    let value_index = data_section.add(value);
+
+    let variable_symbol = KOSymbol::new(
+        symstrtab.add(name),
+        value_index,
+        value_size as u16,
+        SymBind::Global,
+        SymType::NoType,
+        data_section.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("variable.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(variable_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a _start function that was never made global
+fn local_start_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Local,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("local.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,82 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{run, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_ko_file};
+
+#[test]
+fn link_inputs_from_response_file() {
+    // The response file lists main.ko, which is found next to it rather than in the working directory
+    write_start_ko("./tests/response/main.ko");
+
+    if let Err(e) = run(&config("@./tests/response/inputs.txt")) {
+        eprintln!("{}", e);
+        panic!("Failed to link inputs from a response file");
+    }
+}
+
+#[test]
+fn missing_response_file() {
+    match run(&config("@./tests/response/missing.txt")) {
+        Err(LinkError::IOError(file_name, _)) => {
+            assert_eq!(file_name, "./tests/response/missing.txt")
+        }
+        Err(e) => panic!("Expected an I/O error, found: {}", e),
+        Ok(_) => panic!("Linking with a missing response file should fail"),
+    }
+}
+
+fn config(response_file: &str) -> CLIConfig {
+    config_builder("./tests/response.ksm")
+        .with_input_path(response_file)
+        .build()
+}
+
+fn write_start_ko(path: &str) {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, path);
+}
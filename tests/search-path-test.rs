@@ -0,0 +1,100 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_ko_file;
+
+#[test]
+fn find_input_in_search_path() {
+    std::fs::create_dir_all("./tests/search").expect("Search directory could not be created");
+    write_start_ko("./tests/search/search-start.ko");
+
+    let config = CLIConfig::builder("./tests/search.ksm")
+        .with_search_path("./tests/search-missing")
+        .with_search_path("./tests/search")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add("search-start.ko");
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link an input from the search path");
+    }
+
+    assert_eq!(driver.function_offsets()["_start"], 1);
+}
+
+#[test]
+fn report_searched_paths() {
+    let config = CLIConfig::builder("./tests/search.ksm")
+        .with_search_path("./tests/search-missing")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add("nowhere.ko");
+
+    match driver.link() {
+        Err(LinkError::InputNotFoundError(input_name, searched)) => {
+            assert_eq!(input_name, "nowhere.ko");
+            assert_eq!(
+                searched,
+                vec!["nowhere.ko", "./tests/search-missing/nowhere.ko"]
+            );
+        }
+        Err(e) => panic!("Expected an input not found error, found: {}", e),
+        Ok(_) => panic!("Linking an input that doesn't exist should fail"),
+    }
+}
+
+/// Writes a KO file with nothing but a _start function
+fn write_start_ko(path: &str) {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("start.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_ko_file(ko, path);
+}
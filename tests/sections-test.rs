@@ -0,0 +1,194 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::sections::CodeType;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn split_code_sections() {
+    let config = CLIConfig::builder("./tests/sections.ksm")
+        .with_split_sections(true)
+        .with_verbosity(LogLevel::MAX)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link split sections");
+        }
+    };
+
+    let section_len = |section_type: CodeType| {
+        ksm_file
+            .code_sections()
+            .find(|section| section.section_type == section_type)
+            .map(|section| section.instructions().count())
+            .unwrap_or_default()
+    };
+
+    // lbrt and helper
+    assert_eq!(section_len(CodeType::Function), 2);
+    // _init
+    assert_eq!(section_len(CodeType::Initialization), 1);
+    // _start
+    assert_eq!(section_len(CodeType::Main), 3);
+
+    assert_eq!(
+        Driver::code_section_lengths(&ksm_file),
+        vec![
+            (CodeType::Function, 2),
+            (CodeType::Initialization, 1),
+            (CodeType::Main, 3)
+        ]
+    );
+
+    // Offsets run through the sections in the order they are written
+    let offset = |name: &str| {
+        driver
+            .summary()
+            .unwrap()
+            .retained_functions
+            .iter()
+            .find(|func| func.name == name)
+            .and_then(|func| func.offset)
+            .unwrap()
+    };
+
+    assert_eq!(offset("helper"), 1);
+    assert_eq!(offset("_init"), 2);
+    assert_eq!(offset("_start"), 3);
+
+    let function_offsets = driver.function_offsets();
+
+    assert_eq!(function_offsets.len(), 3);
+    assert_eq!(function_offsets["helper"], 1);
+    assert_eq!(function_offsets["_init"], 2);
+    assert_eq!(function_offsets["_start"], 3);
+}
+
+#[test]
+fn unsplit_code_sections() {
+    let config = CLIConfig::builder("./tests/unsplit-sections.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link without splitting sections");
+        }
+    };
+
+    // Everything, even _init, is in the main section, but the other two are still written
+    assert_eq!(
+        Driver::code_section_lengths(&ksm_file),
+        vec![
+            (CodeType::Function, 0),
+            (CodeType::Initialization, 0),
+            (CodeType::Main, 6)
+        ]
+    );
+}
+
+/// Creates a KO file with an _init, a _start that calls helper, and helper
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut init = ko.new_func_section("_init");
+    let mut start = ko.new_func_section("_start");
+    let mut helper = ko.new_func_section("helper");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    init.add(Instr::OneOp(Opcode::Ret, zero_index));
+    helper.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    let call_instr = start.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let helper_symbol = KOSymbol::new(
+        symstrtab.add("helper"),
+        DataIdx::PLACEHOLDER,
+        helper.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        helper.section_index(),
+    );
+    let helper_symbol_index = symtab.add(helper_symbol);
+
+    reld_section.add(ReldEntry::new(
+        start.section_index(),
+        call_instr,
+        OperandIndex::One,
+        helper_symbol_index,
+    ));
+
+    let init_symbol = KOSymbol::new(
+        symstrtab.add("_init"),
+        DataIdx::PLACEHOLDER,
+        init.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        init.section_index(),
+    );
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(init_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(init);
+    ko.add_func_section(start);
+    ko.add_func_section(helper);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
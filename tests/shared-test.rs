@@ -0,0 +1,280 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::LinkError;
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn reject_init_cycle() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("cycle.ko"), cycle_ko());
+
+    match driver.link() {
+        Err(LinkError::InitCycleError(cycle)) => {
+            assert_eq!(cycle, vec!["_init", "setup", "_init"]);
+        }
+        Err(e) => panic!("Expected an init cycle error, found: {}", e),
+        Ok(_) => panic!("Linking an _init cycle should fail"),
+    }
+}
+
+#[test]
+fn list_exports() {
+    let mut driver = Driver::new(config());
+
+    driver.add_file(String::from("library.ko"), library_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link shared library");
+    }
+
+    let mut exports = driver.exports();
+    exports.sort();
+
+    // unused is never referenced from _init, so it isn't in the output to be imported
+    assert_eq!(exports, vec!["_init", "setup", "version"]);
+}
+
+#[test]
+fn hide_symbols_from_exports() {
+    let mut config = config();
+    config.hide_symbols = vec![String::from("setup")];
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("library.ko"), library_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link shared library");
+    }
+
+    let mut exports = driver.exports();
+    exports.sort();
+
+    // setup is still called by _init, it just isn't exported
+    assert_eq!(exports, vec!["_init", "version"]);
+}
+
+#[test]
+fn print_export_visibility() {
+    let mut config = config();
+    config.hide_symbols = vec![String::from("setup")];
+    config.print_exports = true;
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("library.ko"), library_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link shared library");
+    }
+
+    // unused was removed, so it is neither
+    assert_eq!(
+        driver.export_report(),
+        ["_init   EXPORTED", "setup   HIDDEN", "version EXPORTED"]
+    );
+}
+
+#[test]
+fn reject_hidden_init() {
+    let mut config = config();
+    config.hide_symbols = vec![String::from("_init")];
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("library.ko"), library_ko());
+
+    match driver.link() {
+        Err(LinkError::HiddenInitFunctionError) => {}
+        Err(e) => panic!("Expected a hidden _init error, found: {}", e),
+        Ok(_) => panic!("Hiding _init should fail"),
+    }
+}
+
+fn config() -> CLIConfig {
+    config_builder("./tests/shared.ksm")
+        .with_shared(true)
+        .with_verbosity(LogLevel::MAX)
+        .build()
+}
+
+/// Creates a KO file where `_init` calls `setup`, along with a global value and an unused function
+fn library_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut init = ko.new_func_section("_init");
+    let mut setup = ko.new_func_section("setup");
+    let mut unused = ko.new_func_section("unused");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+    let version_index = data_section.add(KOSValue::Int16(2));
+
+    let setup_symbol = KOSymbol::new(
+        symstrtab.add("setup"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::Func,
+        setup.section_index(),
+    );
+    let setup_symbol_index = symtab.add(setup_symbol);
+
+    for (name, section_index) in [
+        ("_init", init.section_index()),
+        ("unused", unused.section_index()),
+    ] {
+        symtab.add(KOSymbol::new(
+            symstrtab.add(name),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Global,
+            SymType::Func,
+            section_index,
+        ));
+    }
+
+    symtab.add(KOSymbol::new(
+        symstrtab.add("version"),
+        version_index,
+        2,
+        SymBind::Global,
+        SymType::NoType,
+        data_section.section_index(),
+    ));
+
+    let ret_0 = Instr::OneOp(Opcode::Ret, zero_index);
+
+    let init_call = init.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    init.add(ret_0);
+    setup.add(ret_0);
+    unused.add(ret_0);
+
+    reld_section.add(ReldEntry::new(
+        init.section_index(),
+        init_call,
+        OperandIndex::One,
+        setup_symbol_index,
+    ));
+
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("library.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+    symtab.add(file_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(init);
+    ko.add_func_section(setup);
+    ko.add_func_section(unused);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file where `_init` calls `setup`, which calls back into `_init`
+fn cycle_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut init = ko.new_func_section("_init");
+    let mut setup = ko.new_func_section("setup");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let init_symbol = KOSymbol::new(
+        symstrtab.add("_init"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::Func,
+        init.section_index(),
+    );
+    let init_symbol_index = symtab.add(init_symbol);
+
+    let setup_symbol = KOSymbol::new(
+        symstrtab.add("setup"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::Func,
+        setup.section_index(),
+    );
+    let setup_symbol_index = symtab.add(setup_symbol);
+
+    let call = Instr::TwoOp(Opcode::Call, DataIdx::PLACEHOLDER, null_value_index);
+    let ret_0 = Instr::OneOp(Opcode::Ret, zero_index);
+
+    let init_call = init.add(call);
+    init.add(ret_0);
+
+    let setup_call = setup.add(call);
+    setup.add(ret_0);
+
+    reld_section.add(ReldEntry::new(
+        init.section_index(),
+        init_call,
+        OperandIndex::One,
+        setup_symbol_index,
+    ));
+    reld_section.add(ReldEntry::new(
+        setup.section_index(),
+        setup_call,
+        OperandIndex::One,
+        init_symbol_index,
+    ));
+
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("cycle.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+    symtab.add(file_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(init);
+    ko.add_func_section(setup);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,109 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::Instr as KSMInstr;
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    KOSValue, Opcode,
+};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn link_single_file() {
+    let config = CLIConfig::builder("./tests/single-file.ksm")
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), start_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link a single file");
+        }
+    };
+
+    let summary = driver.summary().unwrap();
+
+    assert_eq!(summary.retained_functions.len(), 1);
+    assert_eq!(summary.retained_functions[0].name, "_start");
+    assert!(summary.eliminated_functions.is_empty());
+
+    let offsets = driver.function_offsets();
+
+    assert_eq!(offsets.len(), 1);
+    assert_eq!(offsets["_start"], 1);
+
+    let instructions: Vec<KSMInstr> = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions().copied())
+        .collect();
+
+    // The label reset, followed by _start exactly as it was written
+    let opcodes: Vec<Opcode> = instructions
+        .iter()
+        .map(|instr| match instr {
+            KSMInstr::ZeroOp(opcode) => *opcode,
+            KSMInstr::OneOp(opcode, _) => *opcode,
+            KSMInstr::TwoOp(opcode, _, _) => *opcode,
+        })
+        .collect();
+
+    assert_eq!(opcodes, vec![Opcode::Lbrt, Opcode::Push, Opcode::Eop]);
+
+    match instructions[1] {
+        KSMInstr::OneOp(Opcode::Push, op) => {
+            assert_eq!(ksm_file.arg_section.get(op), Some(&KOSValue::Int16(42)));
+        }
+        instr => panic!("Expected a push, found: {:?}", instr),
+    }
+}
+
+/// Creates a KO file with only a _start function, which references nothing outside of itself
+fn start_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let value_index = data_section.add(KOSValue::Int16(42));
+
+    start.add(Instr::OneOp(Opcode::Push, value_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("start.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
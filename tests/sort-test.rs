@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+const HELPER_COUNT: usize = 4;
+
+#[test]
+fn sorted_layout_ignores_input_order() {
+    let (forward, forward_offsets) = link_helpers(true, false);
+    let (reversed, reversed_offsets) = link_helpers(true, true);
+
+    assert_eq!(forward, reversed);
+    assert_eq!(forward_offsets, reversed_offsets);
+
+    // _start is pinned to the front, and then every helper follows in name order
+    let mut names: Vec<&String> = forward_offsets.keys().collect();
+    names.sort_by_key(|name| forward_offsets[*name]);
+
+    assert_eq!(
+        names,
+        vec!["_start", "helper0", "helper1", "helper2", "helper3"]
+    );
+}
+
+#[test]
+fn unsorted_layout_follows_input_order() {
+    let (forward, _) = link_helpers(false, false);
+    let (reversed, _) = link_helpers(false, true);
+
+    assert_ne!(forward, reversed);
+}
+
+fn config(sort_functions: bool) -> CLIConfig {
+    config_builder("./tests/sort.ksm")
+        .with_sort_functions(sort_functions)
+        .with_verbosity(LogLevel::MAX)
+        .build()
+}
+
+/// Links main.ko against every helper, added in reverse order if asked, and returns the bytes of
+/// the resulting KSM file along with the offset of each function
+fn link_helpers(sort_functions: bool, reverse: bool) -> (Vec<u8>, HashMap<String, usize>) {
+    let mut driver = Driver::new(config(sort_functions));
+
+    let mut helpers: Vec<usize> = (0..HELPER_COUNT).collect();
+
+    if reverse {
+        helpers.reverse();
+    }
+
+    for i in helpers {
+        driver.add_file(format!("helper{}.ko", i), helper_ko(i));
+    }
+
+    driver.add_file(String::from("main.ko"), main_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link helpers");
+        }
+    };
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    ksm_file.write(&mut file_buffer);
+
+    (file_buffer, driver.function_offsets())
+}
+
+/// Creates a KO file whose _start calls every helper in reverse order
+fn main_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    for i in (0..HELPER_COUNT).rev() {
+        let helper_symbol = KOSymbol::new(
+            symstrtab.add(format!("helper{}", i)),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let helper_symbol_index = symtab.add(helper_symbol);
+
+        let call_instr = start.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            helper_symbol_index,
+        ));
+    }
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a single global function helper{index} that returns its own index
+fn helper_ko(index: usize) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let func_name = format!("helper{}", index);
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut func = ko.new_func_section(&func_name);
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    let index_value = data_section.add(KOSValue::Int16(index as i16));
+
+    func.add(Instr::OneOp(Opcode::Ret, index_value));
+
+    let func_symbol = KOSymbol::new(
+        symstrtab.add(&func_name),
+        DataIdx::PLACEHOLDER,
+        func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(format!("{}.kasm", func_name)),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(func_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
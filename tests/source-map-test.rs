@@ -0,0 +1,203 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn map_instructions_to_functions() {
+    let config = CLIConfig::builder("./tests/source-map.ksm").build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("main.ko"), main_ko(&["helper"]));
+    driver.add_file(
+        String::from("lib.ko"),
+        local_helper_ko("lib.kasm", "helper"),
+    );
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link");
+    }
+
+    let source_map = driver.source_map();
+
+    let described: Vec<(usize, usize, String)> = source_map
+        .iter()
+        .map(|entry| (entry.start, entry.end, entry.to_string()))
+        .collect();
+
+    // _start calls helper, which calls the local _add
+    assert_eq!(
+        described,
+        vec![
+            (1, 4, String::from("_start in main.kasm")),
+            (4, 6, String::from("helper in lib.kasm")),
+            (6, 8, String::from("_add in lib.kasm")),
+        ]
+    );
+
+    let symbolize = |offset: usize| {
+        source_map
+            .iter()
+            .find(|entry| entry.contains(offset))
+            .map(|entry| entry.func_name.as_str())
+    };
+
+    assert_eq!(symbolize(3), Some("_start"));
+    assert_eq!(symbolize(7), Some("_add"));
+    assert_eq!(symbolize(8), None);
+}
+
+/// Creates a KO file whose _start calls each of the given global functions
+fn main_ko(helpers: &[&str]) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    for helper in helpers {
+        let helper_symbol = KOSymbol::new(
+            symstrtab.add(*helper),
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        );
+        let helper_symbol_index = symtab.add(helper_symbol);
+
+        let call_instr = start.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            helper_symbol_index,
+        ));
+    }
+
+    start.add(Instr::OneOp(Opcode::Push, zero_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("main.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file with a global function that calls a local function _add, which is the same
+/// in every file
+fn local_helper_ko(source_file_name: &str, global_name: &str) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut global_func = ko.new_func_section(global_name);
+    let mut add_func = ko.new_func_section("_add");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let zero_index = data_section.add(KOSValue::Int16(0));
+
+    let add_symbol = KOSymbol::new(
+        symstrtab.add("_add"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Local,
+        SymType::Func,
+        add_func.section_index(),
+    );
+    let add_symbol_index = symtab.add(add_symbol);
+
+    let call_instr = global_func.add(Instr::TwoOp(
+        Opcode::Call,
+        DataIdx::PLACEHOLDER,
+        null_value_index,
+    ));
+    global_func.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    add_func.add(Instr::ZeroOp(Opcode::Add));
+    add_func.add(Instr::OneOp(Opcode::Ret, zero_index));
+
+    reld_section.add(ReldEntry::new(
+        global_func.section_index(),
+        call_instr,
+        OperandIndex::One,
+        add_symbol_index,
+    ));
+
+    let global_symbol = KOSymbol::new(
+        symstrtab.add(global_name),
+        DataIdx::PLACEHOLDER,
+        global_func.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        global_func.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add(source_file_name),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(global_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(global_func);
+    ko.add_func_section(add_func);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    write_and_parse(ko)
+}
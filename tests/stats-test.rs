@@ -0,0 +1,42 @@
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn count_argument_values_by_type() {
+    let config = CLIConfig::builder("./tests/stats.ksm")
+        .with_print_stats(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", Some("helper")));
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None));
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with --stats");
+        }
+    };
+
+    let stats = &driver.summary().unwrap().argument_values;
+
+    let types: Vec<(&str, usize, usize)> = stats
+        .iter()
+        .map(|stat| (stat.value_type.as_str(), stat.count, stat.bytes))
+        .collect();
+
+    // The label reset and the call to helper are both labels, which are strings
+    assert_eq!(
+        types,
+        vec![("String", 2, 14), ("Int16", 1, 3), ("Null", 1, 1)]
+    );
+
+    // The section header isn't a value
+    let total_bytes: usize = stats.iter().map(|stat| stat.bytes).sum();
+    assert_eq!(total_bytes, ksm_file.arg_section.size_bytes() - 3);
+}
@@ -0,0 +1,192 @@
+use kerbalobjects::ko::sections::{DataIdx, StringIdx};
+use kerbalobjects::ko::symbols::{OperandIndex, SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::{
+    ko::{
+        symbols::{KOSymbol, ReldEntry},
+        Instr, KOFile,
+    },
+    KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, ProcessingError};
+use klinker::driver::reader::Reader;
+
+mod common;
+
+use common::write_and_parse;
+
+/// The kinds of symbol that are each read in a different place
+#[derive(Debug, Clone, Copy)]
+enum BadSymbol {
+    /// A global value that no instruction uses
+    UnreferencedGlobal,
+    /// An external symbol that no instruction uses
+    UnreferencedExtern,
+    /// An external function that _start calls
+    ReferencedExtern,
+    /// The file symbol itself
+    File,
+}
+
+#[test]
+fn out_of_range_name_indices() {
+    let kinds = [
+        BadSymbol::UnreferencedGlobal,
+        BadSymbol::UnreferencedExtern,
+        BadSymbol::ReferencedExtern,
+        BadSymbol::File,
+    ];
+
+    // The string table has a leading null and 2 names, so everything from 3 on is past its end
+    let name_indices = [3, 4, 255, 256, 4096, u16::MAX as usize, u32::MAX as usize];
+
+    for kind in kinds {
+        for name_index in name_indices {
+            let (kofile, symbol_index) = bad_symbol_ko(kind, name_index);
+
+            let result = std::panic::catch_unwind(|| {
+                Reader::process_file(String::from("bad.ko"), kofile, false, false)
+            })
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Reading a {:?} with name index {} panicked",
+                    kind, name_index
+                )
+            });
+
+            match (kind, result) {
+                (BadSymbol::File, Err(LinkError::MissingFileSymbolNameError(_, symbol, name))) => {
+                    assert_eq!((symbol, name), (symbol_index, name_index));
+                }
+                (
+                    BadSymbol::File,
+                    Err(LinkError::FileContextError(
+                        _,
+                        ProcessingError::MissingSymbolNameError(..),
+                    )),
+                ) => panic!("The file symbol should have its own error"),
+                (
+                    _,
+                    Err(
+                        LinkError::FileContextError(
+                            _,
+                            ProcessingError::MissingSymbolNameError(symbol, name),
+                        )
+                        | LinkError::FuncContextError(
+                            _,
+                            ProcessingError::MissingSymbolNameError(symbol, name),
+                        ),
+                    ),
+                ) => {
+                    assert_eq!((symbol, name), (symbol_index, name_index));
+                }
+                (_, Err(e)) => panic!(
+                    "Expected a missing symbol name error for a {:?} with name index {}, found: {}",
+                    kind, name_index, e
+                ),
+                (_, Ok(_)) => panic!(
+                    "Reading a {:?} with name index {} should fail",
+                    kind, name_index
+                ),
+            }
+        }
+    }
+}
+
+/// Creates a KO file with a _start function and one symbol of the given kind whose name index is
+/// past the end of .symstrtab, returning it along with that symbol's index
+fn bad_symbol_ko(kind: BadSymbol, name_index: usize) -> (KOFile, usize) {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+    let mut reld_section = ko.new_reld_section(".reld");
+
+    let null_value_index = data_section.add(KOSValue::Null);
+    let value_index = data_section.add(KOSValue::Int16(42));
+
+    let start_name = symstrtab.add("_start");
+    let file_name = symstrtab.add("bad.kasm");
+
+    let bad_name = StringIdx::from(name_index);
+
+    let bad_symbol = match kind {
+        BadSymbol::UnreferencedGlobal => KOSymbol::new(
+            bad_name,
+            value_index,
+            2,
+            SymBind::Global,
+            SymType::NoType,
+            data_section.section_index(),
+        ),
+        BadSymbol::UnreferencedExtern | BadSymbol::ReferencedExtern => KOSymbol::new(
+            bad_name,
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Extern,
+            SymType::Func,
+            data_section.section_index(),
+        ),
+        BadSymbol::File => KOSymbol::new(
+            bad_name,
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Global,
+            SymType::File,
+            SectionIdx::NULL,
+        ),
+    };
+
+    let bad_symbol_index = symtab.add(bad_symbol);
+
+    if let BadSymbol::ReferencedExtern = kind {
+        let call_instr = start.add(Instr::TwoOp(
+            Opcode::Call,
+            DataIdx::PLACEHOLDER,
+            null_value_index,
+        ));
+
+        reld_section.add(ReldEntry::new(
+            start.section_index(),
+            call_instr,
+            OperandIndex::One,
+            bad_symbol_index,
+        ));
+    }
+
+    start.add(Instr::OneOp(Opcode::Push, value_index));
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        start_name,
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+
+    symtab.add(start_symbol);
+
+    // Only one file symbol is allowed, so the bad one takes its place
+    if !matches!(kind, BadSymbol::File) {
+        symtab.add(KOSymbol::new(
+            file_name,
+            DataIdx::PLACEHOLDER,
+            0,
+            SymBind::Global,
+            SymType::File,
+            SectionIdx::NULL,
+        ));
+    }
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+    ko.add_reld_section(reld_section);
+
+    (write_and_parse(ko), usize::from(bad_symbol_index))
+}
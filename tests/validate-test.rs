@@ -0,0 +1,121 @@
+use kerbalobjects::ksm::sections::{
+    ArgIndex, ArgumentSection, CodeSection, CodeType, DebugRange, DebugSection,
+};
+use kerbalobjects::ksm::{Instr, KSMFile, KSMFileBuilder};
+use kerbalobjects::{KOSValue, Opcode};
+use klinker::driver::debug::debug_entry;
+use klinker::driver::errors::LinkError;
+use klinker::driver::validate::validate;
+
+#[test]
+fn accept_valid_program() {
+    let mut arg_section = ArgumentSection::new();
+
+    let begin = arg_section.add(KOSValue::String(String::from("@0001")));
+    let target = arg_section.add(KOSValue::String(String::from("@0003")));
+    let zero = arg_section.add(KOSValue::Int16(0));
+
+    let ksm_file = program(
+        arg_section,
+        vec![
+            Instr::OneOp(Opcode::Lbrt, begin),
+            Instr::TwoOp(Opcode::Call, target, zero),
+            Instr::ZeroOp(Opcode::Eop),
+            Instr::OneOp(Opcode::Ret, zero),
+        ],
+    );
+
+    if let Err(e) = validate(&ksm_file) {
+        panic!("A valid program should pass validation: {}", e);
+    }
+}
+
+#[test]
+fn reject_label_out_of_range() {
+    let mut arg_section = ArgumentSection::new();
+
+    let begin = arg_section.add(KOSValue::String(String::from("@0001")));
+    let target = arg_section.add(KOSValue::String(String::from("@0099")));
+    let zero = arg_section.add(KOSValue::Int16(0));
+
+    let ksm_file = program(
+        arg_section,
+        vec![
+            Instr::OneOp(Opcode::Lbrt, begin),
+            Instr::TwoOp(Opcode::Call, target, zero),
+            Instr::ZeroOp(Opcode::Eop),
+        ],
+    );
+
+    match validate(&ksm_file) {
+        Err(LinkError::ValidationError(message)) => assert!(message.contains("@0099")),
+        Err(e) => panic!("Expected a validation error, found: {}", e),
+        Ok(_) => panic!("A label past the last instruction should fail validation"),
+    }
+}
+
+#[test]
+fn accept_label_like_data() {
+    let mut arg_section = ArgumentSection::new();
+
+    let begin = arg_section.add(KOSValue::String(String::from("@0001")));
+    let data = arg_section.add(KOSValue::String(String::from("@9999")));
+
+    // Only the operand of a jump or call is a label, this is just a string
+    let ksm_file = program(
+        arg_section,
+        vec![
+            Instr::OneOp(Opcode::Lbrt, begin),
+            Instr::OneOp(Opcode::Push, data),
+            Instr::ZeroOp(Opcode::Eop),
+        ],
+    );
+
+    if let Err(e) = validate(&ksm_file) {
+        panic!(
+            "A string that looks like a label should pass validation: {}",
+            e
+        );
+    }
+}
+
+#[test]
+fn reject_missing_argument() {
+    let mut arg_section = ArgumentSection::new();
+
+    let begin = arg_section.add(KOSValue::String(String::from("@0001")));
+
+    let ksm_file = program(
+        arg_section,
+        vec![
+            Instr::OneOp(Opcode::Lbrt, begin),
+            Instr::OneOp(Opcode::Push, ArgIndex::from(200usize)),
+            Instr::ZeroOp(Opcode::Eop),
+        ],
+    );
+
+    match validate(&ksm_file) {
+        Err(LinkError::ValidationError(message)) => assert!(message.contains("200")),
+        Err(e) => panic!("Expected a validation error, found: {}", e),
+        Ok(_) => panic!("An operand outside of the argument section should fail validation"),
+    }
+}
+
+fn program(arg_section: ArgumentSection, instructions: Vec<Instr>) -> KSMFile {
+    let mut code_section = CodeSection::new(CodeType::Main);
+
+    for instr in instructions {
+        code_section.add(instr);
+    }
+
+    let debug_section =
+        DebugSection::new(debug_entry(1, [DebugRange::new(2, 4)]).expect("Range is not empty"));
+
+    KSMFileBuilder::new()
+        .with_arg_section(arg_section)
+        .with_code_section(CodeSection::new(CodeType::Function))
+        .with_code_section(CodeSection::new(CodeType::Initialization))
+        .with_code_section(code_section)
+        .with_debug_section(debug_section)
+        .finish()
+}
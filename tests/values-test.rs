@@ -0,0 +1,115 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::{Instr as KSMInstr, KSMFile};
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    BufferIterator, KOSValue, Opcode,
+};
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::write_and_parse;
+
+#[test]
+fn every_value_round_trips() {
+    let values = every_value();
+
+    let config = CLIConfig::builder("./tests/values.ksm")
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("values.ko"), values_ko(&values));
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link every kind of value");
+        }
+    };
+
+    let mut file_buffer = Vec::with_capacity(2048);
+    ksm_file.write(&mut file_buffer);
+
+    let mut buffer_iter = BufferIterator::new(&file_buffer);
+    let parsed = KSMFile::parse_gzipped(&mut buffer_iter).expect("Error reading KSM file");
+
+    let pushed: Vec<&KOSValue> = parsed
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions().copied())
+        .filter_map(|instr| match instr {
+            KSMInstr::OneOp(Opcode::Push, op) => parsed.arg_section.get(op),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(pushed, values.iter().collect::<Vec<_>>());
+}
+
+/// One of each kind of value, including the pairs that hold the same thing but are different
+/// types to kOS
+fn every_value() -> Vec<KOSValue> {
+    vec![
+        KOSValue::Null,
+        KOSValue::Bool(true),
+        KOSValue::Byte(7),
+        KOSValue::Int16(-300),
+        KOSValue::Int32(70000),
+        KOSValue::Float(1.5),
+        KOSValue::Double(-2.25),
+        KOSValue::String(String::from("text")),
+        KOSValue::ArgMarker,
+        KOSValue::ScalarInt(70000),
+        KOSValue::ScalarDouble(-2.25),
+        KOSValue::BoolValue(true),
+        KOSValue::StringValue(String::from("text")),
+    ]
+}
+
+/// Creates a KO file whose _start pushes each of the given values
+fn values_ko(values: &[KOSValue]) -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    for value in values {
+        let value_index = data_section.add(value.clone());
+        start.add(Instr::OneOp(Opcode::Push, value_index));
+    }
+
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("values.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,72 @@
+use clap::Parser;
+use klinker::driver::log::{LogLevel, PhaseTimer};
+use klinker::CLIConfig;
+
+#[test]
+fn count_verbose_flags() {
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm", "-vv"]);
+
+    assert_eq!(config.verbosity, 2);
+
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm"]);
+
+    assert_eq!(config.verbosity, 0);
+}
+
+#[test]
+fn debug_is_max_verbosity() {
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm", "--debug"]);
+
+    assert_eq!(config.verbosity, LogLevel::MAX);
+
+    let result = CLIConfig::try_parse_from(["kld", "main.ko", "-o", "main.ksm", "-v", "--debug"]);
+
+    assert!(
+        result.is_err(),
+        "-v and --debug should not be used together"
+    );
+}
+
+#[test]
+fn phase_timer_records_in_order() {
+    let mut timer = PhaseTimer::start();
+
+    timer.finish("read");
+    timer.finish("emit");
+
+    let names: Vec<&str> = timer.phases().iter().map(|(name, _)| *name).collect();
+
+    assert_eq!(names, vec!["read", "emit"]);
+    assert!(timer.to_string().starts_with("read: "));
+}
+
+#[test]
+fn quiet_silences_reporter() {
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm", "--quiet"]);
+
+    assert!(config.quiet);
+    assert!(config.reporter().is_quiet());
+
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm", "-q", "--werror"]);
+
+    assert!(config.reporter().is_quiet());
+    assert!(config.werror);
+
+    let config = CLIConfig::parse_from(["kld", "main.ko", "-o", "main.ksm"]);
+
+    assert!(!config.reporter().is_quiet());
+}
+
+#[test]
+fn quiet_conflicts_with_verbose() {
+    for flag in ["-v", "-vv", "--debug"] {
+        let result =
+            CLIConfig::try_parse_from(["kld", "main.ko", "-o", "main.ksm", "--quiet", flag]);
+
+        assert!(
+            result.is_err(),
+            "--quiet and {} should not be used together",
+            flag
+        );
+    }
+}
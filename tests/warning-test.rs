@@ -0,0 +1,267 @@
+use kerbalobjects::ko::sections::DataIdx;
+use kerbalobjects::ko::symbols::{SymBind, SymType};
+use kerbalobjects::ko::SectionIdx;
+use kerbalobjects::ksm::{Instr as KSMInstr, IntSize, KSMFile};
+use kerbalobjects::{
+    ko::{symbols::KOSymbol, Instr, KOFile},
+    BufferIterator, KOSValue, Opcode,
+};
+use klinker::driver::errors::{LinkError, LinkWarning};
+use klinker::driver::log::LogLevel;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::{config_builder, write_and_parse};
+
+#[test]
+fn warn_on_empty_entry_point() {
+    let mut driver = Driver::new(config(false));
+
+    driver.add_file(String::from("empty.ko"), empty_start_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link empty entry point");
+    }
+
+    let warnings: Vec<&LinkWarning> = driver.warnings().collect();
+
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0],
+        LinkWarning::EmptyEntryPointWarning(entry_point) if entry_point == "_start"
+    ));
+}
+
+#[test]
+fn werror_fails_link() {
+    let mut driver = Driver::new(config(true));
+
+    driver.add_file(String::from("empty.ko"), empty_start_ko());
+
+    match driver.link() {
+        Err(e @ LinkError::WarningsAsErrors(_)) => {
+            let message = e.to_string();
+            let LinkError::WarningsAsErrors(warnings) = e else {
+                unreachable!()
+            };
+
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].contains("_start"));
+
+            // The warning itself is part of the error, on its own line
+            assert_eq!(message.lines().nth(1), Some(warnings[0].as_str()));
+        }
+        Err(e) => panic!("Expected warnings to be errors, found: {}", e),
+        Ok(_) => panic!("Linking with warnings and --werror should fail"),
+    }
+}
+
+#[test]
+fn warn_on_wide_arg_index() {
+    let mut config = config(false);
+    config.warn_addr_width = true;
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("wide.ko"), many_values_ko());
+
+    if let Err(e) = driver.link() {
+        eprintln!("{}", e);
+        panic!("Failed to link many values");
+    }
+
+    let warnings: Vec<&LinkWarning> = driver.warnings().collect();
+
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0],
+        LinkWarning::ArgIndexWidthWarning(2, size) if *size > 255
+    ));
+}
+
+#[test]
+fn force_wider_addr_width() {
+    let config = CLIConfig::builder("./tests/warning.ksm")
+        .with_addr_width(3)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("wide.ko"), many_values_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with a wider address width");
+        }
+    };
+
+    assert_eq!(ksm_file.arg_section.num_index_bytes(), IntSize::Three);
+
+    // The padding shouldn't change what any operand refers to
+    let mut file_buffer = Vec::new();
+    ksm_file.write(&mut file_buffer);
+
+    let mut buffer_iter = BufferIterator::new(&file_buffer);
+    let parsed = KSMFile::parse_gzipped(&mut buffer_iter).expect("Error reading KSM file");
+
+    let pushed: Vec<&KOSValue> = parsed
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions().copied())
+        .filter_map(|instr| match instr {
+            KSMInstr::OneOp(Opcode::Push, op) => parsed.arg_section.get(op),
+            _ => None,
+        })
+        .collect();
+
+    let expected: Vec<KOSValue> = (0..100).map(KOSValue::Int32).collect();
+
+    assert_eq!(pushed, expected.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn force_widest_addr_width() {
+    let config = CLIConfig::builder("./tests/warning.ksm")
+        .with_addr_width(4)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("wide.ko"), many_values_ko());
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with the widest address width");
+        }
+    };
+
+    assert_eq!(ksm_file.arg_section.num_index_bytes(), IntSize::Four);
+
+    // kerbalobjects switches to four byte operands once the section is past 1677215 bytes
+    assert!(ksm_file.arg_section.size_bytes() > 1677215);
+
+    let pushed: Vec<&KOSValue> = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions().copied())
+        .filter_map(|instr| match instr {
+            KSMInstr::OneOp(Opcode::Push, op) => ksm_file.arg_section.get(op),
+            _ => None,
+        })
+        .collect();
+
+    let expected: Vec<KOSValue> = (0..100).map(KOSValue::Int32).collect();
+
+    assert_eq!(pushed, expected.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn reject_narrow_addr_width() {
+    let config = CLIConfig::builder("./tests/warning.ksm")
+        .with_addr_width(1)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("wide.ko"), many_values_ko());
+
+    match driver.link() {
+        Err(LinkError::AddrWidthTooSmallError(1, 2, size)) => assert!(size > 255),
+        Err(e) => panic!("Expected an address width error, found: {}", e),
+        Ok(_) => panic!("Operands too narrow for the argument section should fail"),
+    }
+}
+
+fn config(werror: bool) -> CLIConfig {
+    config_builder("./tests/warning.ksm")
+        .with_werror(werror)
+        .with_verbosity(LogLevel::MAX)
+        .build()
+}
+
+/// Creates a KO file whose _start function has no instructions
+fn empty_start_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    data_section.add(KOSValue::Null);
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("empty.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
+
+/// Creates a KO file whose _start pushes enough distinct values to need two byte operands
+fn many_values_ko() -> KOFile {
+    let mut ko = KOFile::new();
+
+    let mut data_section = ko.new_data_section(".data");
+    let mut start = ko.new_func_section("_start");
+    let mut symtab = ko.new_symtab(".symtab");
+    let mut symstrtab = ko.new_strtab(".symstrtab");
+
+    for value in 0..100 {
+        let value_index = data_section.add(KOSValue::Int32(value));
+        start.add(Instr::OneOp(Opcode::Push, value_index));
+    }
+
+    start.add(Instr::ZeroOp(Opcode::Eop));
+
+    let start_symbol = KOSymbol::new(
+        symstrtab.add("_start"),
+        DataIdx::PLACEHOLDER,
+        start.size() as u16,
+        SymBind::Global,
+        SymType::Func,
+        start.section_index(),
+    );
+    let file_symbol = KOSymbol::new(
+        symstrtab.add("wide.kasm"),
+        DataIdx::PLACEHOLDER,
+        0,
+        SymBind::Global,
+        SymType::File,
+        SectionIdx::NULL,
+    );
+
+    symtab.add(file_symbol);
+    symtab.add(start_symbol);
+
+    ko.add_data_section(data_section);
+    ko.add_func_section(start);
+    ko.add_str_tab(symstrtab);
+    ko.add_sym_tab(symtab);
+
+    write_and_parse(ko)
+}
@@ -0,0 +1,69 @@
+use kerbalobjects::ksm::Instr as KSMInstr;
+use kerbalobjects::{KOSValue, Opcode};
+use klinker::driver::errors::LinkError;
+use klinker::{driver::Driver, CLIConfig};
+
+mod common;
+
+use common::func_ko;
+
+#[test]
+fn wrap_redirects_references() {
+    let config = CLIConfig::builder("./tests/wrap.ksm")
+        .with_wrap_symbol("helper")
+        .with_validate(true)
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", Some("helper")));
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None));
+    driver.add_file(
+        String::from("wrap.ko"),
+        func_ko("__wrap_helper", Some("__real_helper")),
+    );
+
+    let ksm_file = match driver.link() {
+        Ok(ksm_file) => ksm_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("Failed to link with a wrapped symbol");
+        }
+    };
+
+    let offsets = driver.function_offsets();
+    let label = |name: &str| KOSValue::String(format!("@{:0>4}", offsets[name]));
+
+    let calls: Vec<&KOSValue> = ksm_file
+        .code_sections()
+        .flat_map(|code_section| code_section.instructions().copied())
+        .filter_map(|instr| match instr {
+            KSMInstr::TwoOp(Opcode::Call, op, _) => ksm_file.arg_section.get(op),
+            _ => None,
+        })
+        .collect();
+
+    // _start is always first, so its call comes first, and it reaches the wrapper instead
+    assert_eq!(calls.len(), 2);
+    assert_eq!(*calls[0], label("__wrap_helper"));
+    // While the wrapper's call to __real_helper reaches the original
+    assert_eq!(*calls[1], label("helper"));
+}
+
+#[test]
+fn reject_missing_wrapper() {
+    let config = CLIConfig::builder("./tests/wrap-missing.ksm")
+        .with_wrap_symbol("helper")
+        .build();
+
+    let mut driver = Driver::new(config);
+
+    driver.add_file(String::from("start.ko"), func_ko("_start", Some("helper")));
+    driver.add_file(String::from("helper.ko"), func_ko("helper", None));
+
+    match driver.link() {
+        Err(LinkError::MissingWrapSymbolError(name)) => assert_eq!(name, "helper"),
+        Err(e) => panic!("Expected a missing wrapper error, found: {}", e),
+        Ok(_) => panic!("Linking should fail without __wrap_helper"),
+    }
+}